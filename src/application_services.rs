@@ -1,20 +1,139 @@
-use std::{
-    collections::{HashMap, HashSet},
-    fs,
-};
+use std::{collections::HashMap, fs};
 
-use anyhow::{Context, Result};
-use serde::Deserialize;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
+use time::{macros::format_description, Date, Duration};
 
-use crate::domain_models::{BountyHunterPlanning, GalaxyRoutes, PlanetCatalog};
+use crate::domain_models::{
+    AutonomyOdds, BountyHunterPlanning, CountdownAnalysis, Departure, EmpireCaptureModel,
+    EncounterDays, EncounterPolicy, FleetPlan, GalaxyRoutes, HunterPlacement, Itinerary, Mission,
+    MissionPlan, Patrol, PlanetCatalog, PlanetId, Pursuit, RefuelModel, RobustObjective,
+    RobustPlan, RouteDelays, Scenario, SensitivityReport, Sighting, Waypoint,
+};
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct MillenniumFalconData {
     pub autonomy: u64,
-    pub departure: String,
-    pub arrival: String,
+    /// planet, or list of planets, where the Millennium Falcon can start
+    pub departure: OneOrMany<DepartureData>,
+    /// planet, or list of planets, where the Millennium Falcon can go
+    pub arrival: OneOrMany<String>,
     pub routes_db: PathBuf,
+    /// how the Millennium Falcon refuels. Default to a full refuel in a single day
+    #[serde(default)]
+    pub refuel: Option<RefuelData>,
+    /// planets where the Millennium Falcon must never land
+    #[serde(default)]
+    pub forbidden_planets: Vec<String>,
+    /// planets the Millennium Falcon has to visit before reaching the arrival planet
+    #[serde(default)]
+    pub waypoints: Vec<WaypointData>,
+    /// if true, the waypoints have to be visited in the order of the list
+    #[serde(default)]
+    pub ordered_waypoints: bool,
+    /// day of the Empire plan when the mission starts. The start days of the departures are
+    /// relative to it, while the countdown and the days of the bounty hunters stay relative to
+    /// the day 0 of the Empire plan
+    #[serde(default)]
+    pub start_day: u64,
+    /// calendar date (YYYY-MM-DD) of the day 0 of the Empire plan, used to date the itineraries
+    #[serde(default)]
+    pub day_0_date: Option<String>,
+    /// ships escorting the Millennium Falcon, for the fleet planning
+    #[serde(default)]
+    pub escorts: Vec<EscortData>,
+}
+
+/// A ship escorting the Millennium Falcon. It has the same mission, but its own autonomy and departure,
+/// and optionally its own arrival
+#[derive(Debug, Deserialize, Clone)]
+pub struct EscortData {
+    pub name: String,
+    pub autonomy: u64,
+    pub departure: OneOrMany<DepartureData>,
+    #[serde(default)]
+    pub arrival: Option<OneOrMany<String>>,
+}
+
+/// Name of the Millennium Falcon in the fleet plans
+pub const MILLENNIUM_FALCON_NAME: &str = "Millennium Falcon";
+
+/// Either a single value, or a list of values
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+impl<T> OneOrMany<T> {
+    pub fn as_slice(&self) -> &[T] {
+        match self {
+            OneOrMany::One(value) => std::slice::from_ref(value),
+            OneOrMany::Many(values) => values,
+        }
+    }
+}
+
+/// JSON representation of a `Departure`: either a planet name, starting on day 0,
+/// or a planet with its start day
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum DepartureData {
+    Planet(String),
+    Scheduled { planet: String, start_day: u64 },
+}
+
+impl From<&DepartureData> for Departure {
+    fn from(value: &DepartureData) -> Self {
+        match value {
+            DepartureData::Planet(planet) => Departure {
+                planet: planet.clone(),
+                start_day: 0,
+            },
+            DepartureData::Scheduled { planet, start_day } => Departure {
+                planet: planet.clone(),
+                start_day: *start_day,
+            },
+        }
+    }
+}
+
+/// JSON representation of a `Waypoint`
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct WaypointData {
+    pub planet: String,
+    #[serde(default)]
+    pub deadline: Option<u64>,
+}
+
+impl From<&WaypointData> for Waypoint {
+    fn from(value: &WaypointData) -> Self {
+        Waypoint {
+            planet: value.planet.clone(),
+            deadline: value.deadline,
+        }
+    }
+}
+
+/// JSON representation of a `RefuelModel`
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RefuelData {
+    Full { days: u64 },
+    Partial { fuel_per_day: u64 },
+}
+
+impl From<&RefuelData> for RefuelModel {
+    fn from(value: &RefuelData) -> Self {
+        match value {
+            RefuelData::Full { days } => RefuelModel::Full { days: *days },
+            RefuelData::Partial { fuel_per_day } => RefuelModel::Partial {
+                fuel_per_day: *fuel_per_day,
+            },
+        }
+    }
 }
 
 impl MillenniumFalconData {
@@ -30,14 +149,200 @@ impl MillenniumFalconData {
     }
 
     pub fn parse(text: &str) -> Result<Self> {
-        serde_json::from_str(text).context("Unable to parse millennium falcon data")
+        let data: MillenniumFalconData =
+            serde_json::from_str(text).context("Unable to parse millennium falcon data")?;
+        match data.refuel {
+            Some(RefuelData::Full { days: 0 }) => return Err(anyhow!("refuel can't take 0 days")),
+            Some(RefuelData::Partial { fuel_per_day: 0 }) => {
+                return Err(anyhow!("refuel need to give at least 1 fuel per day"))
+            }
+            _ => {}
+        }
+        if let Some(day_0_date) = &data.day_0_date {
+            Calendar::parse(day_0_date)?;
+        }
+        if data.departure.as_slice().is_empty() || data.arrival.as_slice().is_empty() {
+            return Err(anyhow!("at least one departure and one arrival are needed"));
+        }
+        let mut names = vec![MILLENNIUM_FALCON_NAME];
+        for escort in data.escorts.iter() {
+            if names.contains(&escort.name.as_str()) {
+                return Err(anyhow!("two ships of the fleet are named {}", escort.name));
+            }
+            names.push(&escort.name);
+            if escort.departure.as_slice().is_empty()
+                || escort
+                    .arrival
+                    .as_ref()
+                    .is_some_and(|arrival| arrival.as_slice().is_empty())
+            {
+                return Err(anyhow!(
+                    "{} needs at least one departure and one arrival",
+                    escort.name
+                ));
+            }
+        }
+        let departures = data
+            .departure
+            .as_slice()
+            .iter()
+            .chain(
+                data.escorts
+                    .iter()
+                    .flat_map(|escort| escort.departure.as_slice()),
+            )
+            .map(Departure::from);
+        for departure in departures {
+            if departure.start_day.checked_add(data.start_day).is_none() {
+                return Err(anyhow!(
+                    "the departure from {} starts too late after the start day {}",
+                    departure.planet,
+                    data.start_day
+                ));
+            }
+        }
+        let mandatory_planets = data
+            .departure
+            .as_slice()
+            .iter()
+            .chain(
+                data.escorts
+                    .iter()
+                    .flat_map(|escort| escort.departure.as_slice()),
+            )
+            .map(|departure| match departure {
+                DepartureData::Planet(planet) => planet,
+                DepartureData::Scheduled { planet, .. } => planet,
+            })
+            .chain(data.arrival.as_slice())
+            .chain(
+                data.escorts
+                    .iter()
+                    .filter_map(|escort| escort.arrival.as_ref())
+                    .flat_map(|arrival| arrival.as_slice()),
+            )
+            .chain(data.waypoints.iter().map(|waypoint| &waypoint.planet));
+        for planet in mandatory_planets {
+            if data.forbidden_planets.contains(planet) {
+                return Err(anyhow!(
+                    "{planet} can't be forbidden, a ship of the fleet may have to go there"
+                ));
+            }
+        }
+        Ok(data)
+    }
+
+    /// Combine the Millennium Falcon data with the countdown of the Empire to define the mission
+    pub fn to_mission(&self, countdown: u64) -> Mission {
+        Mission {
+            autonomy: self.autonomy,
+            departures: self.to_departures(&self.departure),
+            arrivals: self.arrival.as_slice().to_vec(),
+            countdown,
+            refuel_model: self
+                .refuel
+                .as_ref()
+                .map(RefuelModel::from)
+                .unwrap_or_default(),
+            forbidden_planets: self.forbidden_planets.clone(),
+            waypoints: self.waypoints.iter().map(Waypoint::from).collect(),
+            ordered_waypoints: self.ordered_waypoints,
+        }
+    }
+
+    /// Departures of a ship, shifted to the start day of the mission.
+    /// `parse` checks that the shifted days don't overflow
+    fn to_departures(&self, departures: &OneOrMany<DepartureData>) -> Vec<Departure> {
+        departures
+            .as_slice()
+            .iter()
+            .map(Departure::from)
+            .map(|departure| Departure {
+                start_day: departure.start_day + self.start_day,
+                ..departure
+            })
+            .collect()
+    }
+
+    /// Missions of the ships of the fleet, with their names: the Millennium Falcon first, then its escorts
+    pub fn to_fleet(&self, countdown: u64) -> Vec<(String, Mission)> {
+        let mission = self.to_mission(countdown);
+        let escort_missions = self.escorts.iter().map(|escort| {
+            (
+                escort.name.clone(),
+                Mission {
+                    autonomy: escort.autonomy,
+                    departures: self.to_departures(&escort.departure),
+                    arrivals: escort
+                        .arrival
+                        .as_ref()
+                        .map(|arrival| arrival.as_slice().to_vec())
+                        .unwrap_or_else(|| mission.arrivals.clone()),
+                    ..mission.clone()
+                },
+            )
+        });
+        std::iter::once((MILLENNIUM_FALCON_NAME.to_string(), mission.clone()))
+            .chain(escort_missions)
+            .collect()
+    }
+
+    /// Calendar mapping the days of the Empire plan to dates, if a date is given for the day 0
+    pub fn calendar(&self) -> Option<Calendar> {
+        // the date was checked when parsing
+        self.day_0_date
+            .as_ref()
+            .and_then(|day_0_date| Calendar::parse(day_0_date).ok())
     }
 }
 
-#[derive(Debug, Deserialize)]
+/// Mapping between the days of the Empire plan and the dates of the galactic calendar
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Calendar {
+    day_0: Date,
+}
+
+impl Calendar {
+    /// Build the calendar from the date (YYYY-MM-DD) of the day 0
+    pub fn parse(day_0_date: &str) -> Result<Self> {
+        let day_0 = Date::parse(day_0_date, format_description!("[year]-[month]-[day]"))
+            .with_context(|| format!("Unable to parse date {day_0_date}, expected YYYY-MM-DD"))?;
+        Ok(Calendar { day_0 })
+    }
+
+    /// Date of a day of the Empire plan, formatted as YYYY-MM-DD
+    pub fn date(&self, day: u64) -> String {
+        let date = i64::try_from(day)
+            .ok()
+            .and_then(|day| self.day_0.checked_add(Duration::days(day)));
+        match date {
+            Some(date) => date
+                .format(format_description!("[year]-[month]-[day]"))
+                .unwrap_or_else(|_| date.to_string()),
+            None => format!("day {day} after {}", self.day_0),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct EmpireData {
     pub countdown: u64,
     pub bounty_hunters: Vec<BountyHunter>,
+    /// probability of being captured by the bounty hunters of a planet, when not specified by the bounty hunter
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub capture_probabilities: HashMap<String, f64>,
+    /// bounty hunters coming back regularly to a planet
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub patrols: Vec<PatrolData>,
+    /// routes closed by the blockades of the Empire
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub route_closures: Vec<RouteClosureData>,
+    /// if defined, bounty hunters go after the Millennium Falcon once it has been spotted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pursuit: Option<PursuitData>,
+    /// which days on a planet with bounty hunters count as an encounter. Default to every day
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encounter_policy: Option<EncounterPolicyData>,
 }
 
 impl EmpireData {
@@ -47,13 +352,112 @@ impl EmpireData {
     }
 
     pub fn parse(text: &str) -> Result<Self> {
-        serde_json::from_str(text).context("Unable to parse empire data")
+        let data: EmpireData = serde_json::from_str(text).context("Unable to parse empire data")?;
+        data.validate()?;
+        Ok(data)
+    }
+
+    /// Check the values that can't be checked by the parsing
+    fn validate(&self) -> Result<()> {
+        let probabilities = self
+            .bounty_hunters
+            .iter()
+            .flat_map(|hunter| [hunter.capture_probability, hunter.presence_probability])
+            .chain(
+                self.patrols
+                    .iter()
+                    .flat_map(|patrol| [patrol.capture_probability, patrol.presence_probability]),
+            )
+            .flatten()
+            .chain(self.capture_probabilities.values().copied())
+            .chain(
+                self.pursuit
+                    .as_ref()
+                    .and_then(|pursuit| pursuit.capture_probability),
+            );
+        for probability in probabilities {
+            if !(0. ..=1.).contains(&probability) {
+                return Err(anyhow!(
+                    "probability {probability} should be between 0 and 1"
+                ));
+            }
+        }
+        for patrol in self.patrols.iter() {
+            if patrol.every == Some(0) {
+                return Err(anyhow!(
+                    "the patrol of {} can't come every 0 days",
+                    patrol.planet
+                ));
+            }
+            if patrol
+                .last_day
+                .is_some_and(|last_day| last_day < patrol.first_day)
+            {
+                return Err(anyhow!(
+                    "the patrol of {} ends before its first day",
+                    patrol.planet
+                ));
+            }
+        }
+        for closure in self.route_closures.iter() {
+            let valid = match (closure.first_day, closure.last_day) {
+                (Some(first_day), Some(last_day)) => first_day <= last_day,
+                (None, None) => true,
+                _ => false,
+            };
+            if !valid {
+                return Err(anyhow!(
+                    "the closure of the route from {} to {} needs a first_day before its last_day",
+                    closure.origin,
+                    closure.destination
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Capture probabilities of the bounty hunters of each planet, without the specific ones of the listed bounty hunters
+    pub fn capture_model(&self, planet_id_map: &PlanetCatalog) -> EmpireCaptureModel {
+        let mut capture_model = EmpireCaptureModel::default();
+        for (planet, capture_probability) in self.capture_probabilities.iter() {
+            if let Some(planet_id) = planet_id_map.get(planet) {
+                capture_model
+                    .planets
+                    .insert(*planet_id, *capture_probability);
+            }
+        }
+        capture_model
+    }
+
+    /// Return a copy of the Empire data with more bounty hunters, certainly present
+    pub fn with_bounty_hunters(
+        &self,
+        hunter_placement: &HunterPlacement,
+        planet_catalog: &PlanetCatalog,
+    ) -> EmpireData {
+        let mut empire_data = self.clone();
+        empire_data
+            .bounty_hunters
+            .extend(
+                hunter_placement
+                    .hunters
+                    .iter()
+                    .map(|(planet, day)| BountyHunter {
+                        planet: planet_name(planet, planet_catalog),
+                        day: *day,
+                        capture_probability: None,
+                        presence_probability: None,
+                    }),
+            );
+        empire_data
     }
 
     pub fn to_bounty_hunters_planning(
         &self,
         planet_id_map: &PlanetCatalog,
     ) -> BountyHunterPlanning {
+        let mut capture_model = self.capture_model(planet_id_map);
+
         let mut planet_to_days = HashMap::new();
         for hunter in self.bounty_hunters.iter() {
             let planet_id = match planet_id_map.get(&hunter.planet) {
@@ -63,50 +467,643 @@ impl EmpireData {
                     continue;
                 }
             };
-            planet_to_days
+            // if the same bounty hunter is reported several times, keep the most reliable report
+            let presence_probability = hunter.presence_probability.unwrap_or(1.);
+            let p = planet_to_days
                 .entry(*planet_id)
-                .or_insert(HashSet::new())
-                .insert(hunter.day);
+                .or_insert(HashMap::new())
+                .entry(hunter.day)
+                .or_insert(presence_probability);
+            *p = p.max(presence_probability);
+            if let Some(capture_probability) = hunter.capture_probability {
+                // if the same bounty hunter is listed several times, keep the most dangerous one
+                let p = capture_model
+                    .hunters
+                    .entry((*planet_id, hunter.day))
+                    .or_insert(capture_probability);
+                *p = p.max(capture_probability);
+            }
         }
-        BountyHunterPlanning::new(planet_to_days)
+        let mut planning =
+            BountyHunterPlanning::with_presence_probabilities(planet_to_days, &capture_model);
+        for patrol in self.patrols.iter() {
+            let planet_id = match planet_id_map.get(&patrol.planet) {
+                Some(v) => v,
+                None => {
+                    println!("Patrol outside of map");
+                    continue;
+                }
+            };
+            let sighting = Sighting {
+                presence_probability: patrol.presence_probability.unwrap_or(1.),
+                capture_probability: patrol
+                    .capture_probability
+                    .unwrap_or_else(|| capture_model.planet_capture_probability(planet_id)),
+            };
+            planning.add_patrol(
+                *planet_id,
+                Patrol {
+                    first_day: patrol.first_day,
+                    last_day: patrol.last_day,
+                    period: patrol.every.unwrap_or(1),
+                    sighting,
+                },
+            );
+        }
+        for closure in self.route_closures.iter() {
+            let (Some(origin_id), Some(destination_id)) = (
+                planet_id_map.get(&closure.origin),
+                planet_id_map.get(&closure.destination),
+            ) else {
+                println!("Route closure outside of map");
+                continue;
+            };
+            let ranges = closure
+                .days
+                .iter()
+                .map(|day| (*day, *day))
+                .chain(closure.first_day.zip(closure.last_day));
+            for (first_day, last_day) in ranges {
+                planning.add_route_closure(*origin_id, *destination_id, first_day, last_day);
+                if closure.both_ways {
+                    planning.add_route_closure(*destination_id, *origin_id, first_day, last_day);
+                }
+            }
+        }
+        if let Some(encounter_policy) = &self.encounter_policy {
+            planning.set_encounter_policy(EncounterPolicy::from(encounter_policy));
+        }
+        if let Some(pursuit) = &self.pursuit {
+            planning.set_pursuit(Pursuit {
+                capture_probability: pursuit
+                    .capture_probability
+                    .unwrap_or(capture_model.default.capture_probability),
+            });
+        }
+        planning
     }
 }
 
+/// Possible plan of the Empire: the Empire data, with the likelihood of the scenario relative to the other ones
 #[derive(Debug, Deserialize)]
+pub struct EmpireScenarioData {
+    /// Default to 1, all the scenarios are equally likely
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+    #[serde(flatten)]
+    pub empire_data: EmpireData,
+}
+
+fn default_weight() -> f64 {
+    1.
+}
+
+impl EmpireScenarioData {
+    /// Parse a list of scenarios
+    pub fn parse_scenarios(text: &str) -> Result<Vec<Self>> {
+        let scenarios: Vec<EmpireScenarioData> =
+            serde_json::from_str(text).context("Unable to parse empire scenarios")?;
+        if scenarios.is_empty() {
+            return Err(anyhow!("at least one scenario is needed"));
+        }
+        for scenario in scenarios.iter() {
+            if scenario.weight < 0. {
+                return Err(anyhow!("the weight of a scenario can't be negative"));
+            }
+            scenario.empire_data.validate()?;
+        }
+        Ok(scenarios)
+    }
+
+    pub fn to_scenario(&self, planet_id_map: &PlanetCatalog) -> Scenario {
+        Scenario {
+            hunter_planning: self.empire_data.to_bounty_hunters_planning(planet_id_map),
+            countdown: self.empire_data.countdown,
+            weight: self.weight,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct BountyHunter {
     pub planet: String,
     pub day: u64,
+    /// probability of being captured by this bounty hunter
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capture_probability: Option<f64>,
+    /// confidence of the Rebellion intelligence in this report. Default to 1, the bounty hunter is certainly there
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub presence_probability: Option<f64>,
 }
 
-#[derive(Debug)]
+/// Bounty hunters present on a planet every `every` days (every day by default) from `first_day`,
+/// until `last_day` if specified
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PatrolData {
+    pub planet: String,
+    pub first_day: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_day: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub every: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capture_probability: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub presence_probability: Option<f64>,
+}
+
+/// JSON representation of an `EncounterPolicy`
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub struct EncounterPolicyData {
+    #[serde(default)]
+    pub days: EncounterDaysData,
+    #[serde(default)]
+    pub consecutive_days_once: bool,
+}
+
+/// JSON representation of `EncounterDays`
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum EncounterDaysData {
+    Arrival,
+    Present,
+    #[default]
+    PresentAndRefuel,
+}
+
+impl From<&EncounterPolicyData> for EncounterPolicy {
+    fn from(value: &EncounterPolicyData) -> Self {
+        EncounterPolicy {
+            days: match value.days {
+                EncounterDaysData::Arrival => EncounterDays::Arrival,
+                EncounterDaysData::Present => EncounterDays::Present,
+                EncounterDaysData::PresentAndRefuel => EncounterDays::PresentAndRefuel,
+            },
+            consecutive_days_once: value.consecutive_days_once,
+        }
+    }
+}
+
+/// Bounty hunters going after the Millennium Falcon once it has been spotted
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PursuitData {
+    /// probability of being captured by the pursuers. Default to the default capture probability
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capture_probability: Option<f64>,
+}
+
+/// Route from `origin` to `destination` closed on some `days` and from `first_day` to `last_day` (included).
+/// The route from `destination` to `origin` is closed too when `both_ways` is set
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct RouteClosureData {
+    pub origin: String,
+    pub destination: String,
+    #[serde(default)]
+    pub both_ways: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub days: Vec<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_day: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_day: Option<u64>,
+}
+
+/// JSON representation of a `MissionPlan`, using the planet names instead of their ids
+#[derive(Debug, Serialize, PartialEq)]
+pub struct MissionPlanData {
+    pub odds: f64,
+    /// departure and arrival planets chosen among the ones of the mission
+    pub departure: Option<String>,
+    pub arrival: Option<String>,
+    pub arrival_day: Option<u64>,
+    /// date of the arrival, only set when the mission has a calendar
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arrival_date: Option<String>,
+    pub n_bounty_hunter: Option<u64>,
+    pub itinerary: Option<Vec<StepData>>,
+}
+
+/// JSON representation of a `RobustObjective`
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RobustObjectiveData {
+    #[default]
+    Expected,
+    WorstCase,
+}
+
+impl From<RobustObjectiveData> for RobustObjective {
+    fn from(value: RobustObjectiveData) -> Self {
+        match value {
+            RobustObjectiveData::Expected => RobustObjective::Expected,
+            RobustObjectiveData::WorstCase => RobustObjective::WorstCase,
+        }
+    }
+}
+
+/// JSON representation of a `RobustPlan`. The odds are the ones of the objective, and the bounty hunters met
+/// are given for each scenario, in the order of the scenarios
+#[derive(Debug, Serialize, PartialEq)]
+pub struct RobustPlanData {
+    pub odds: f64,
+    pub expected_odds: f64,
+    pub worst_case_odds: f64,
+    /// odds in each scenario
+    pub scenario_odds: Vec<f64>,
+    pub departure: Option<String>,
+    pub arrival: Option<String>,
+    pub arrival_day: Option<u64>,
+    /// date of the arrival, only set when the mission has a calendar
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arrival_date: Option<String>,
+    /// number of bounty hunters met in each scenario
+    pub scenario_n_bounty_hunter: Vec<u64>,
+    pub itinerary: Option<Vec<RobustStepData>>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct RobustStepData {
+    pub planet: String,
+    pub day: u64,
+    pub fuel: u64,
+    pub action: String,
+    /// true if bounty hunters are met at this step, in each scenario
+    pub bounty_hunters: Vec<bool>,
+    /// date of the step, only set when the mission has a calendar
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+}
+
+impl RobustPlanData {
+    pub fn from_robust_plan(
+        robust_plan: &RobustPlan,
+        objective: RobustObjective,
+        planet_catalog: &PlanetCatalog,
+    ) -> Self {
+        let itinerary = robust_plan.itinerary.as_ref();
+        RobustPlanData {
+            odds: match objective {
+                RobustObjective::Expected => robust_plan.expected_probability_of_success,
+                RobustObjective::WorstCase => robust_plan.worst_case_probability_of_success,
+            },
+            expected_odds: robust_plan.expected_probability_of_success,
+            worst_case_odds: robust_plan.worst_case_probability_of_success,
+            scenario_odds: robust_plan.probabilities_of_success.clone(),
+            departure: itinerary
+                .and_then(|itinerary| itinerary.departure_planet())
+                .map(|planet_id| planet_name(planet_id, planet_catalog)),
+            arrival: itinerary
+                .and_then(|itinerary| itinerary.arrival_planet())
+                .map(|planet_id| planet_name(planet_id, planet_catalog)),
+            arrival_day: itinerary.and_then(|itinerary| itinerary.arrival_day()),
+            arrival_date: None,
+            scenario_n_bounty_hunter: robust_plan
+                .bounty_hunters
+                .iter()
+                .map(|bounty_hunters| bounty_hunters.iter().filter(|met| **met).count() as u64)
+                .collect(),
+            itinerary: itinerary.map(|itinerary| {
+                itinerary
+                    .steps()
+                    .iter()
+                    .enumerate()
+                    .map(|(i, step)| RobustStepData {
+                        planet: planet_name(&step.planet, planet_catalog),
+                        day: step.day,
+                        fuel: step.fuel,
+                        action: step.action.to_string(),
+                        bounty_hunters: robust_plan
+                            .bounty_hunters
+                            .iter()
+                            .map(|bounty_hunters| bounty_hunters[i])
+                            .collect(),
+                        date: None,
+                    })
+                    .collect()
+            }),
+        }
+    }
+
+    /// Add the dates of the arrival and of the steps, if there is a calendar
+    pub fn with_dates(mut self, calendar: Option<&Calendar>) -> Self {
+        if let Some(calendar) = calendar {
+            self.arrival_date = self.arrival_day.map(|day| calendar.date(day));
+            for step in self.itinerary.iter_mut().flatten() {
+                step.date = Some(calendar.date(step.day));
+            }
+        }
+        self
+    }
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct StepData {
+    pub planet: String,
+    pub day: u64,
+    pub fuel: u64,
+    pub action: String,
+    pub bounty_hunter: bool,
+    /// date of the step, only set when the mission has a calendar
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+}
+
+impl MissionPlanData {
+    pub fn from_mission_plan(mission_plan: &MissionPlan, planet_catalog: &PlanetCatalog) -> Self {
+        MissionPlanData {
+            odds: mission_plan.probability_of_success,
+            departure: mission_plan
+                .itinerary
+                .as_ref()
+                .and_then(|itinerary| itinerary.departure_planet())
+                .map(|planet_id| planet_name(planet_id, planet_catalog)),
+            arrival: mission_plan
+                .itinerary
+                .as_ref()
+                .and_then(|itinerary| itinerary.arrival_planet())
+                .map(|planet_id| planet_name(planet_id, planet_catalog)),
+            arrival_day: mission_plan
+                .itinerary
+                .as_ref()
+                .and_then(|itinerary| itinerary.arrival_day()),
+            arrival_date: None,
+            n_bounty_hunter: mission_plan
+                .itinerary
+                .as_ref()
+                .map(|itinerary| itinerary.n_bounty_hunter()),
+            itinerary: mission_plan
+                .itinerary
+                .as_ref()
+                .map(|itinerary| itinerary_to_steps_data(itinerary, planet_catalog)),
+        }
+    }
+
+    /// Add the dates of the arrival and of the steps, if there is a calendar
+    pub fn with_dates(mut self, calendar: Option<&Calendar>) -> Self {
+        if let Some(calendar) = calendar {
+            self.arrival_date = self.arrival_day.map(|day| calendar.date(day));
+            for step in self.itinerary.iter_mut().flatten() {
+                step.date = Some(calendar.date(step.day));
+            }
+        }
+        self
+    }
+}
+
+/// JSON representation of a `FleetPlan`, with the plan of each ship
+#[derive(Debug, Serialize, PartialEq)]
+pub struct FleetPlanData {
+    /// odds that at least one ship reaches its arrival planet
+    pub odds_at_least_one: f64,
+    /// odds that all the ships reach their arrival planet
+    pub odds_all: f64,
+    pub ships: Vec<ShipPlanData>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ShipPlanData {
+    pub name: String,
+    #[serde(flatten)]
+    pub mission_plan: MissionPlanData,
+}
+
+impl FleetPlanData {
+    /// `names` are the names of the ships, in the order of the mission plans
+    pub fn from_fleet_plan(
+        fleet_plan: &FleetPlan,
+        names: &[String],
+        planet_catalog: &PlanetCatalog,
+    ) -> Self {
+        FleetPlanData {
+            odds_at_least_one: fleet_plan.probability_at_least_one,
+            odds_all: fleet_plan.probability_all,
+            ships: names
+                .iter()
+                .zip(fleet_plan.mission_plans.iter())
+                .map(|(name, mission_plan)| ShipPlanData {
+                    name: name.clone(),
+                    mission_plan: MissionPlanData::from_mission_plan(mission_plan, planet_catalog),
+                })
+                .collect(),
+        }
+    }
+
+    /// Add the dates of the arrivals and of the steps, if there is a calendar
+    pub fn with_dates(mut self, calendar: Option<&Calendar>) -> Self {
+        self.ships = self
+            .ships
+            .into_iter()
+            .map(|ship| ShipPlanData {
+                mission_plan: ship.mission_plan.with_dates(calendar),
+                ..ship
+            })
+            .collect();
+        self
+    }
+}
+
+/// JSON representation of a `CountdownAnalysis`
+#[derive(Debug, Serialize, PartialEq)]
+pub struct CountdownAnalysisData {
+    pub minimum_countdown: Option<u64>,
+    pub curve: Vec<CountdownOddsData>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct CountdownOddsData {
+    pub countdown: u64,
+    pub odds: f64,
+}
+
+impl From<&CountdownAnalysis> for CountdownAnalysisData {
+    fn from(value: &CountdownAnalysis) -> Self {
+        CountdownAnalysisData {
+            minimum_countdown: value.minimum_countdown,
+            curve: value
+                .curve
+                .iter()
+                .map(|countdown_odds| CountdownOddsData {
+                    countdown: countdown_odds.countdown,
+                    odds: countdown_odds.probability_of_success,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Best odds of the mission for an autonomy of the Millennium Falcon
+#[derive(Debug, Serialize, PartialEq)]
+pub struct AutonomyOddsData {
+    pub autonomy: u64,
+    pub odds: f64,
+}
+
+impl From<&AutonomyOdds> for AutonomyOddsData {
+    fn from(value: &AutonomyOdds) -> Self {
+        AutonomyOddsData {
+            autonomy: value.autonomy,
+            odds: value.probability_of_success,
+        }
+    }
+}
+
+/// Odds of the mission, and the sightings ranked by decreasing impact on them
+#[derive(Debug, Serialize, PartialEq)]
+pub struct SensitivityReportData {
+    pub odds: f64,
+    pub sightings: Vec<SightingImpactData>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct SightingImpactData {
+    pub planet: String,
+    pub day: u64,
+    /// odds of the mission without this sighting
+    pub odds: f64,
+    /// improvement of the odds when removing this sighting
+    pub impact: f64,
+}
+
+impl SensitivityReportData {
+    pub fn from_sensitivity_report(
+        report: &SensitivityReport,
+        planet_catalog: &PlanetCatalog,
+    ) -> Self {
+        SensitivityReportData {
+            odds: report.probability_of_success,
+            sightings: report
+                .sightings
+                .iter()
+                .map(|sighting| SightingImpactData {
+                    planet: planet_name(&sighting.planet, planet_catalog),
+                    day: sighting.day,
+                    odds: sighting.probability_of_success,
+                    impact: sighting.impact,
+                })
+                .collect(),
+        }
+    }
+}
+
+fn planet_name(planet_id: &PlanetId, planet_catalog: &PlanetCatalog) -> String {
+    planet_catalog
+        .name(planet_id)
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| planet_id.to_string())
+}
+
+fn itinerary_to_steps_data(itinerary: &Itinerary, planet_catalog: &PlanetCatalog) -> Vec<StepData> {
+    itinerary
+        .steps()
+        .iter()
+        .map(|step| StepData {
+            planet: planet_name(&step.planet, planet_catalog),
+            day: step.day,
+            fuel: step.fuel,
+            action: step.action.to_string(),
+            bounty_hunter: step.bounty_hunter,
+            date: None,
+        })
+        .collect()
+}
+
+#[derive(Debug, PartialEq)]
 pub struct Route {
     pub origin: String,
     pub destination: String,
     pub travel_time: u64,
+    /// travel time from the destination to the origin, None if the route is one-way
+    pub reverse_travel_time: Option<u64>,
+}
+
+/// Probability for a hyperspace jump from `origin` to `destination` to take `delay` more days than its travel time
+#[derive(Debug, PartialEq)]
+pub struct RouteDelay {
+    pub origin: String,
+    pub destination: String,
+    pub delay: u64,
+    pub probability: f64,
 }
 
-pub fn into_galaxy_routes_and_planet_id(routes: Vec<Route>) -> (GalaxyRoutes, PlanetCatalog) {
+#[derive(Debug, PartialEq)]
+pub struct Planet {
+    pub name: String,
+    pub fuel_depot: bool,
+}
+
+/// Build the galaxy from the routes, and add to the catalog the attributes of the planets.
+/// Planets not reachable by any route are ignored, as the Millennium Falcon can't go there anyway.
+pub fn into_galaxy_routes_and_planet_id(
+    routes: Vec<Route>,
+    planets: Vec<Planet>,
+) -> (GalaxyRoutes, PlanetCatalog) {
     let mut galaxy_routes = GalaxyRoutes::new();
     let mut plannet_id_map = PlanetCatalog::new();
 
     for route in routes {
         let origin_id = plannet_id_map.get_or_insert(route.origin);
         let destination_id = plannet_id_map.get_or_insert(route.destination);
-        galaxy_routes.add_route(origin_id, destination_id, route.travel_time);
+        galaxy_routes.add_directed_route(origin_id, destination_id, route.travel_time);
+        if let Some(reverse_travel_time) = route.reverse_travel_time {
+            galaxy_routes.add_directed_route(destination_id, origin_id, reverse_travel_time);
+        }
+    }
+
+    for planet in planets {
+        if let Some(planet_id) = plannet_id_map.get(&planet.name).copied() {
+            plannet_id_map.set_fuel_depot(&planet_id, planet.fuel_depot);
+        }
     }
 
     (galaxy_routes, plannet_id_map)
 }
 
+/// Build the delays of the routes. Delays of planets not reachable by any route are ignored
+pub fn into_route_delays(
+    route_delays: Vec<RouteDelay>,
+    planet_catalog: &PlanetCatalog,
+) -> Result<RouteDelays> {
+    let mut delays = RouteDelays::new();
+    for route_delay in route_delays {
+        if let (Some(origin_id), Some(destination_id)) = (
+            planet_catalog.get(&route_delay.origin),
+            planet_catalog.get(&route_delay.destination),
+        ) {
+            delays
+                .add_delay(
+                    *origin_id,
+                    *destination_id,
+                    route_delay.delay,
+                    route_delay.probability,
+                )
+                .with_context(|| {
+                    format!(
+                        "Invalid delay of the route from {} to {}",
+                        route_delay.origin, route_delay.destination
+                    )
+                })?;
+        }
+    }
+    Ok(delays)
+}
+
 #[cfg(test)]
 mod test {
 
+    use std::collections::HashMap;
+
     use crate::{
         application_services::BountyHunter,
-        domain_models::{BountyHunterPlanning, GalaxyRoutes, PlanetCatalog},
+        domain_models::{
+            BountyHunterPlanning, Departure, EncounterDays, EncounterPolicy, GalaxyRoutes,
+            HunterPlacement, Mission, PlanetCatalog, Pursuit, RefuelModel, Sighting, Waypoint,
+        },
     };
 
-    use super::{into_galaxy_routes_and_planet_id, EmpireData, Route};
+    use super::{
+        into_galaxy_routes_and_planet_id, into_route_delays, Calendar, EmpireData,
+        EmpireScenarioData, MillenniumFalconData, Planet, Route, RouteDelay,
+    };
 
     #[test]
     fn test_to_bounty_hunters_planning() {
@@ -116,16 +1113,27 @@ mod test {
                 BountyHunter {
                     planet: "Hoth".to_string(),
                     day: 6,
+                    capture_probability: None,
+                    presence_probability: None,
                 },
                 BountyHunter {
                     planet: "Hoth".to_string(),
                     day: 7,
+                    capture_probability: None,
+                    presence_probability: None,
                 },
                 BountyHunter {
                     planet: "Hoth".to_string(),
                     day: 8,
+                    capture_probability: None,
+                    presence_probability: None,
                 },
             ],
+            capture_probabilities: HashMap::new(),
+            patrols: Vec::new(),
+            route_closures: Vec::new(),
+            pursuit: None,
+            encounter_policy: None,
         };
         let planet_id_map = get_planet_id_map();
         let hoth_id = *planet_id_map.get("Hoth").unwrap();
@@ -138,6 +1146,263 @@ mod test {
         assert_eq!(bh_planning, bh_planning_gt);
     }
 
+    #[test]
+    fn test_to_bounty_hunters_planning_capture_probabilities() {
+        let empire_data = EmpireData::parse(
+            r#"{
+                "countdown": 7,
+                "bounty_hunters": [
+                    {"planet": "Hoth", "day": 6, "capture_probability": 0.5},
+                    {"planet": "Hoth", "day": 7},
+                    {"planet": "Endor", "day": 7}
+                ],
+                "capture_probabilities": {"Hoth": 0.3}
+            }"#,
+        )
+        .unwrap();
+        let planet_id_map = get_planet_id_map();
+        let hoth_id = *planet_id_map.get("Hoth").unwrap();
+        let endor_id = *planet_id_map.get("Endor").unwrap();
+        let bh_planning = empire_data.to_bounty_hunters_planning(&planet_id_map);
+        assert_eq!(bh_planning.capture_probability(&hoth_id, 6), Some(0.5));
+        assert_eq!(bh_planning.capture_probability(&hoth_id, 7), Some(0.3));
+        assert_eq!(bh_planning.capture_probability(&endor_id, 7), Some(0.1));
+        assert_eq!(bh_planning.capture_probability(&endor_id, 6), None);
+
+        assert!(EmpireData::parse(
+            r#"{"countdown": 7, "bounty_hunters": [{"planet": "Hoth", "day": 6, "capture_probability": 1.5}]}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_to_bounty_hunters_planning_presence_probabilities() {
+        let empire_data = EmpireData::parse(
+            r#"{
+                "countdown": 7,
+                "bounty_hunters": [
+                    {"planet": "Hoth", "day": 6, "presence_probability": 0.7},
+                    {"planet": "Hoth", "day": 6, "presence_probability": 0.2},
+                    {"planet": "Hoth", "day": 7, "presence_probability": 0.5, "capture_probability": 0.4}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let planet_id_map = get_planet_id_map();
+        let hoth_id = *planet_id_map.get("Hoth").unwrap();
+        let bh_planning = empire_data.to_bounty_hunters_planning(&planet_id_map);
+        assert_eq!(
+            bh_planning.sighting(&hoth_id, 6),
+            Some(Sighting {
+                presence_probability: 0.7,
+                capture_probability: 0.1
+            })
+        );
+        assert_eq!(bh_planning.capture_probability(&hoth_id, 7), Some(0.2));
+
+        assert!(EmpireData::parse(
+            r#"{"countdown": 7, "bounty_hunters": [{"planet": "Hoth", "day": 6, "presence_probability": -0.5}]}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_to_bounty_hunters_planning_patrols() {
+        let empire_data = EmpireData::parse(
+            r#"{
+                "countdown": 100,
+                "bounty_hunters": [{"planet": "Hoth", "day": 5, "capture_probability": 0.5}],
+                "patrols": [
+                    {"planet": "Hoth", "first_day": 2, "every": 3},
+                    {"planet": "Endor", "first_day": 10, "last_day": 40, "presence_probability": 0.5}
+                ],
+                "capture_probabilities": {"Hoth": 0.3}
+            }"#,
+        )
+        .unwrap();
+        let planet_id_map = get_planet_id_map();
+        let hoth_id = *planet_id_map.get("Hoth").unwrap();
+        let endor_id = *planet_id_map.get("Endor").unwrap();
+        let bh_planning = empire_data.to_bounty_hunters_planning(&planet_id_map);
+        assert_eq!(bh_planning.capture_probability(&hoth_id, 1), None);
+        assert_eq!(bh_planning.capture_probability(&hoth_id, 2), Some(0.3));
+        assert_eq!(bh_planning.capture_probability(&hoth_id, 3), None);
+        assert_eq!(bh_planning.capture_probability(&hoth_id, 5), Some(0.5));
+        assert_eq!(
+            bh_planning.capture_probability(&hoth_id, 1_000_001),
+            Some(0.3)
+        );
+        assert_eq!(bh_planning.capture_probability(&endor_id, 9), None);
+        assert_eq!(bh_planning.capture_probability(&endor_id, 10), Some(0.05));
+        assert_eq!(bh_planning.capture_probability(&endor_id, 40), Some(0.05));
+        assert_eq!(bh_planning.capture_probability(&endor_id, 41), None);
+
+        assert!(EmpireData::parse(
+            r#"{"countdown": 7, "bounty_hunters": [], "patrols": [{"planet": "Hoth", "first_day": 2, "every": 0}]}"#
+        )
+        .is_err());
+        assert!(EmpireData::parse(
+            r#"{"countdown": 7, "bounty_hunters": [], "patrols": [{"planet": "Hoth", "first_day": 2, "last_day": 1}]}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_to_bounty_hunters_planning_pursuit() {
+        let planet_id_map = get_planet_id_map();
+        let empire_data =
+            EmpireData::parse(r#"{"countdown": 10, "bounty_hunters": [], "pursuit": {}}"#).unwrap();
+        let bh_planning = empire_data.to_bounty_hunters_planning(&planet_id_map);
+        assert_eq!(
+            bh_planning.pursuit(),
+            Some(&Pursuit {
+                capture_probability: 0.1
+            })
+        );
+
+        let empire_data = EmpireData::parse(
+            r#"{"countdown": 10, "bounty_hunters": [], "pursuit": {"capture_probability": 0.3}}"#,
+        )
+        .unwrap();
+        let bh_planning = empire_data.to_bounty_hunters_planning(&planet_id_map);
+        assert_eq!(
+            bh_planning.pursuit(),
+            Some(&Pursuit {
+                capture_probability: 0.3
+            })
+        );
+        assert_eq!(
+            EmpireData::parse(r#"{"countdown": 10, "bounty_hunters": []}"#)
+                .unwrap()
+                .to_bounty_hunters_planning(&planet_id_map)
+                .pursuit(),
+            None
+        );
+
+        assert!(EmpireData::parse(
+            r#"{"countdown": 10, "bounty_hunters": [], "pursuit": {"capture_probability": 1.5}}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_to_bounty_hunters_planning_encounter_policy() {
+        let planet_id_map = get_planet_id_map();
+        let empire_data = EmpireData::parse(
+            r#"{"countdown": 10, "bounty_hunters": [], "encounter_policy": {"days": "arrival", "consecutive_days_once": true}}"#,
+        )
+        .unwrap();
+        let bh_planning = empire_data.to_bounty_hunters_planning(&planet_id_map);
+        assert_eq!(
+            bh_planning.encounter_policy(),
+            &EncounterPolicy {
+                days: EncounterDays::Arrival,
+                consecutive_days_once: true
+            }
+        );
+
+        let empire_data =
+            EmpireData::parse(r#"{"countdown": 10, "bounty_hunters": [], "encounter_policy": {}}"#)
+                .unwrap();
+        let bh_planning = empire_data.to_bounty_hunters_planning(&planet_id_map);
+        assert_eq!(bh_planning.encounter_policy(), &EncounterPolicy::default());
+
+        assert!(EmpireData::parse(
+            r#"{"countdown": 10, "bounty_hunters": [], "encounter_policy": {"days": "sometimes"}}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_to_bounty_hunters_planning_route_closures() {
+        let empire_data = EmpireData::parse(
+            r#"{
+                "countdown": 10,
+                "bounty_hunters": [],
+                "route_closures": [
+                    {"origin": "Hoth", "destination": "Endor", "days": [2, 4], "both_ways": true},
+                    {"origin": "Tatooine", "destination": "Hoth", "first_day": 3, "last_day": 5}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let planet_id_map = get_planet_id_map();
+        let hoth_id = *planet_id_map.get("Hoth").unwrap();
+        let endor_id = *planet_id_map.get("Endor").unwrap();
+        let tatooine_id = *planet_id_map.get("Tatooine").unwrap();
+        let bh_planning = empire_data.to_bounty_hunters_planning(&planet_id_map);
+        assert!(bh_planning.is_route_closed(&endor_id, &hoth_id, 2));
+        assert!(!bh_planning.is_route_closed(&endor_id, &hoth_id, 3));
+        assert!(bh_planning.is_route_closed(&hoth_id, &endor_id, 4));
+        assert!(!bh_planning.is_route_closed(&tatooine_id, &hoth_id, 2));
+        assert!(bh_planning.is_route_closed(&tatooine_id, &hoth_id, 5));
+        assert!(!bh_planning.is_route_closed(&hoth_id, &tatooine_id, 5));
+        assert!(!bh_planning.is_route_closed(&tatooine_id, &endor_id, 4));
+        assert_eq!(bh_planning.last_closure_day(), Some(5));
+
+        assert!(EmpireData::parse(
+            r#"{"countdown": 7, "bounty_hunters": [], "route_closures": [{"origin": "Hoth", "destination": "Endor", "first_day": 2}]}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_empire_data_with_bounty_hunters() {
+        let empire_data = EmpireData::parse(
+            r#"{
+                "countdown": 10,
+                "bounty_hunters": [{"planet": "Hoth", "day": 5, "presence_probability": 0.5}],
+                "capture_probabilities": {"Endor": 0.3}
+            }"#,
+        )
+        .unwrap();
+        let planet_id_map = get_planet_id_map();
+        let endor_id = *planet_id_map.get("Endor").unwrap();
+        let hunter_placement = HunterPlacement {
+            hunters: vec![(endor_id, 8)],
+            probability_of_success: 0.7,
+            optimal: true,
+        };
+        let empire_data = empire_data.with_bounty_hunters(&hunter_placement, &planet_id_map);
+
+        // the Empire data can be written and read again
+        let json = serde_json::to_string(&empire_data).unwrap();
+        assert_eq!(
+            json,
+            r#"{"countdown":10,"bounty_hunters":[{"planet":"Hoth","day":5,"presence_probability":0.5},{"planet":"Endor","day":8}],"capture_probabilities":{"Endor":0.3}}"#
+        );
+        let bh_planning = EmpireData::parse(&json)
+            .unwrap()
+            .to_bounty_hunters_planning(&planet_id_map);
+        assert_eq!(bh_planning.capture_probability(&endor_id, 8), Some(0.3));
+    }
+
+    #[test]
+    fn test_parse_scenarios() {
+        let scenarios = EmpireScenarioData::parse_scenarios(
+            r#"[
+                {"countdown": 10, "bounty_hunters": [{"planet": "Hoth", "day": 6}], "weight": 3},
+                {"countdown": 8, "bounty_hunters": []}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(scenarios.len(), 2);
+        assert_eq!(scenarios[0].weight, 3.);
+        assert_eq!(scenarios[0].empire_data.bounty_hunters.len(), 1);
+        assert_eq!(scenarios[1].weight, 1.);
+        assert_eq!(scenarios[1].empire_data.countdown, 8);
+
+        assert!(EmpireScenarioData::parse_scenarios("[]").is_err());
+        assert!(EmpireScenarioData::parse_scenarios(
+            r#"[{"countdown": 10, "bounty_hunters": [], "weight": -1}]"#
+        )
+        .is_err());
+        assert!(EmpireScenarioData::parse_scenarios(
+            r#"[{"countdown": 10, "bounty_hunters": [{"planet": "Hoth", "day": 6, "capture_probability": 2}]}]"#
+        )
+        .is_err());
+    }
+
     #[test]
     fn test_into_galaxy_routes_and_planet_id() {
         let routes = vec![
@@ -145,37 +1410,55 @@ mod test {
                 origin: "Tatooine".to_string(),
                 destination: "Dagobah".to_string(),
                 travel_time: 6,
+                reverse_travel_time: Some(6),
             },
             Route {
                 origin: "Dagobah".to_string(),
                 destination: "Endor".to_string(),
                 travel_time: 4,
+                reverse_travel_time: Some(4),
             },
             Route {
                 origin: "Dagobah".to_string(),
                 destination: "Hoth".to_string(),
                 travel_time: 1,
+                reverse_travel_time: Some(1),
             },
             Route {
                 origin: "Hoth".to_string(),
                 destination: "Endor".to_string(),
                 travel_time: 1,
+                reverse_travel_time: Some(1),
             },
             Route {
                 origin: "Tatooine".to_string(),
                 destination: "Hoth".to_string(),
                 travel_time: 6,
+                reverse_travel_time: Some(6),
             },
         ];
-        let (galaxy_route, planet_id_map) = into_galaxy_routes_and_planet_id(routes);
-        let planet_id_map_gt = get_planet_id_map();
+        let planets = vec![
+            Planet {
+                name: "Hoth".to_string(),
+                fuel_depot: false,
+            },
+            Planet {
+                name: "Coruscant".to_string(),
+                fuel_depot: false,
+            },
+        ];
+        let (galaxy_route, planet_id_map) = into_galaxy_routes_and_planet_id(routes, planets);
+        let mut planet_id_map_gt = get_planet_id_map();
 
         let tatooine_id = *planet_id_map.get("Tatooine").unwrap();
         let dagobah_id = *planet_id_map.get("Dagobah").unwrap();
         let endor_id = *planet_id_map.get("Endor").unwrap();
         let hoth_id = *planet_id_map.get("Hoth").unwrap();
 
+        planet_id_map_gt.set_fuel_depot(&hoth_id, false);
         assert_eq!(planet_id_map, planet_id_map_gt);
+        assert!(planet_id_map.has_fuel_depot(&tatooine_id));
+        assert!(!planet_id_map.has_fuel_depot(&hoth_id));
 
         let galaxy_route_gt = GalaxyRoutes::from_hashmap(
             [
@@ -197,6 +1480,258 @@ mod test {
         assert_eq!(galaxy_route, galaxy_route_gt);
     }
 
+    #[test]
+    fn test_into_galaxy_routes_directed() {
+        let routes = vec![
+            Route {
+                origin: "Tatooine".to_string(),
+                destination: "Dagobah".to_string(),
+                travel_time: 6,
+                reverse_travel_time: None,
+            },
+            Route {
+                origin: "Dagobah".to_string(),
+                destination: "Endor".to_string(),
+                travel_time: 4,
+                reverse_travel_time: Some(2),
+            },
+        ];
+        let (galaxy_route, planet_id_map) = into_galaxy_routes_and_planet_id(routes, Vec::new());
+        let tatooine_id = *planet_id_map.get("Tatooine").unwrap();
+        let dagobah_id = *planet_id_map.get("Dagobah").unwrap();
+        let endor_id = *planet_id_map.get("Endor").unwrap();
+
+        let galaxy_route_gt = GalaxyRoutes::from_hashmap(HashMap::from([
+            (tatooine_id, vec![(dagobah_id, 6)]),
+            (dagobah_id, vec![(endor_id, 4)]),
+            (endor_id, vec![(dagobah_id, 2)]),
+        ]))
+        .unwrap();
+        assert_eq!(galaxy_route, galaxy_route_gt);
+    }
+
+    #[test]
+    fn test_into_route_delays() {
+        let planet_id_map = get_planet_id_map();
+        let tatooine_id = *planet_id_map.get("Tatooine").unwrap();
+        let dagobah_id = *planet_id_map.get("Dagobah").unwrap();
+        let route_delay = |origin: &str, delay, probability| RouteDelay {
+            origin: origin.to_string(),
+            destination: "Dagobah".to_string(),
+            delay,
+            probability,
+        };
+
+        let route_delays = into_route_delays(
+            vec![
+                route_delay("Tatooine", 1, 0.2),
+                route_delay("Tatooine", 3, 0.1),
+                // planets not in the galaxy are ignored
+                route_delay("Kamino", 1, 0.5),
+            ],
+            &planet_id_map,
+        )
+        .unwrap();
+        assert_eq!(
+            route_delays.travel_times(&tatooine_id, &dagobah_id, 6),
+            [(6, 0.7), (7, 0.2), (9, 0.1)]
+        );
+        assert_eq!(
+            route_delays.travel_times(&dagobah_id, &tatooine_id, 6),
+            [(6, 1.)]
+        );
+
+        assert!(into_route_delays(
+            vec![
+                route_delay("Tatooine", 1, 0.6),
+                route_delay("Tatooine", 2, 0.6)
+            ],
+            &planet_id_map
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_millennium_falcon_data_refuel() {
+        let data = MillenniumFalconData::parse(
+            r#"{"autonomy": 6, "departure": "Tatooine", "arrival": "Endor", "routes_db": "universe.db"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            data.to_mission(7).refuel_model,
+            RefuelModel::Full { days: 1 }
+        );
+
+        let data = MillenniumFalconData::parse(
+            r#"{"autonomy": 6, "departure": "Tatooine", "arrival": "Endor", "routes_db": "universe.db",
+                "refuel": {"type": "partial", "fuel_per_day": 2}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            data.to_mission(7).refuel_model,
+            RefuelModel::Partial { fuel_per_day: 2 }
+        );
+
+        assert!(MillenniumFalconData::parse(
+            r#"{"autonomy": 6, "departure": "Tatooine", "arrival": "Endor", "routes_db": "universe.db",
+                "refuel": {"type": "full", "days": 0}}"#,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_millennium_falcon_data_constraints() {
+        let data = MillenniumFalconData::parse(
+            r#"{"autonomy": 6, "departure": "Tatooine", "arrival": "Endor", "routes_db": "universe.db",
+                "forbidden_planets": ["Hoth"], "waypoints": [{"planet": "Dagobah", "deadline": 8}]}"#,
+        )
+        .unwrap();
+        let mission = data.to_mission(10);
+        assert_eq!(mission.forbidden_planets, ["Hoth".to_string()]);
+        assert_eq!(
+            mission.waypoints,
+            [Waypoint {
+                planet: "Dagobah".to_string(),
+                deadline: Some(8)
+            }]
+        );
+        assert!(!mission.ordered_waypoints);
+
+        assert!(MillenniumFalconData::parse(
+            r#"{"autonomy": 6, "departure": "Tatooine", "arrival": "Endor", "routes_db": "universe.db",
+                "forbidden_planets": ["Dagobah"], "waypoints": [{"planet": "Dagobah"}]}"#,
+        )
+        .is_err());
+        assert!(MillenniumFalconData::parse(
+            r#"{"autonomy": 6, "departure": "Tatooine", "arrival": "Endor", "routes_db": "universe.db",
+                "forbidden_planets": ["Endor"]}"#,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_millennium_falcon_data_several_departures_and_arrivals() {
+        let data = MillenniumFalconData::parse(
+            r#"{"autonomy": 6, "departure": ["Tatooine", {"planet": "Dagobah", "start_day": 3}],
+                "arrival": ["Endor", "Hoth"], "routes_db": "universe.db"}"#,
+        )
+        .unwrap();
+        let mission = data.to_mission(10);
+        assert_eq!(
+            mission.departures,
+            [
+                Departure {
+                    planet: "Tatooine".to_string(),
+                    start_day: 0
+                },
+                Departure {
+                    planet: "Dagobah".to_string(),
+                    start_day: 3
+                }
+            ]
+        );
+        assert_eq!(mission.arrivals, ["Endor".to_string(), "Hoth".to_string()]);
+
+        assert!(MillenniumFalconData::parse(
+            r#"{"autonomy": 6, "departure": [], "arrival": "Endor", "routes_db": "universe.db"}"#,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_millennium_falcon_data_start_day_and_calendar() {
+        let data = MillenniumFalconData::parse(
+            r#"{"autonomy": 6, "departure": ["Tatooine", {"planet": "Dagobah", "start_day": 3}],
+                "arrival": "Endor", "routes_db": "universe.db",
+                "start_day": 5, "day_0_date": "2024-02-27"}"#,
+        )
+        .unwrap();
+        let mission = data.to_mission(10);
+        assert_eq!(
+            mission.departures,
+            [
+                Departure {
+                    planet: "Tatooine".to_string(),
+                    start_day: 5
+                },
+                Departure {
+                    planet: "Dagobah".to_string(),
+                    start_day: 8
+                }
+            ]
+        );
+        // the countdown stays relative to the day 0 of the Empire plan
+        assert_eq!(mission.countdown, 10);
+
+        let calendar = data.calendar().unwrap();
+        assert_eq!(calendar.date(0), "2024-02-27");
+        assert_eq!(calendar.date(2), "2024-02-29");
+        assert_eq!(calendar.date(5), "2024-03-03");
+
+        assert!(Calendar::parse("27/02/2024").is_err());
+        assert!(MillenniumFalconData::parse(
+            r#"{"autonomy": 6, "departure": "Tatooine", "arrival": "Endor", "routes_db": "universe.db",
+                "day_0_date": "2024-02-30"}"#,
+        )
+        .is_err());
+        // the start day of a departure, shifted by the start day of the mission, must fit in a day
+        assert!(MillenniumFalconData::parse(&format!(
+            r#"{{"autonomy": 6, "departure": {{"planet": "Tatooine", "start_day": {}}},
+                "arrival": "Endor", "routes_db": "universe.db", "start_day": 5}}"#,
+            u64::MAX - 4
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn test_millennium_falcon_data_escorts() {
+        let data = MillenniumFalconData::parse(
+            r#"{"autonomy": 6, "departure": "Tatooine", "arrival": "Endor", "routes_db": "universe.db",
+                "start_day": 2, "forbidden_planets": ["Kamino"],
+                "escorts": [{"name": "Ghost", "autonomy": 3, "departure": "Dagobah"},
+                            {"name": "Outrider", "autonomy": 4, "departure": {"planet": "Hoth", "start_day": 1},
+                             "arrival": ["Dagobah", "Endor"]}]}"#,
+        )
+        .unwrap();
+        let fleet = data.to_fleet(10);
+        assert_eq!(
+            fleet
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>(),
+            ["Millennium Falcon", "Ghost", "Outrider"]
+        );
+        assert_eq!(fleet[0].1, data.to_mission(10));
+        assert_eq!(
+            fleet[1].1,
+            Mission {
+                autonomy: 3,
+                departures: vec![Departure {
+                    planet: "Dagobah".to_string(),
+                    start_day: 2
+                }],
+                ..data.to_mission(10)
+            }
+        );
+        assert_eq!(fleet[2].1.autonomy, 4);
+        assert_eq!(fleet[2].1.departures[0].start_day, 3);
+        assert_eq!(fleet[2].1.arrivals, ["Dagobah", "Endor"]);
+        assert_eq!(fleet[2].1.forbidden_planets, ["Kamino"]);
+
+        // the names of the ships identify them in the fleet plan
+        assert!(MillenniumFalconData::parse(
+            r#"{"autonomy": 6, "departure": "Tatooine", "arrival": "Endor", "routes_db": "universe.db",
+                "escorts": [{"name": "Millennium Falcon", "autonomy": 3, "departure": "Dagobah"}]}"#,
+        )
+        .is_err());
+        assert!(MillenniumFalconData::parse(
+            r#"{"autonomy": 6, "departure": "Tatooine", "arrival": "Endor", "routes_db": "universe.db",
+                "forbidden_planets": ["Dagobah"],
+                "escorts": [{"name": "Ghost", "autonomy": 3, "departure": "Dagobah"}]}"#,
+        )
+        .is_err());
+    }
+
     fn get_planet_id_map() -> PlanetCatalog {
         PlanetCatalog::from_vec(vec![
             "Tatooine".to_string(),
@@ -1,28 +1,370 @@
 use anyhow::Result;
 use millennium_falcon::application_services::into_galaxy_routes_and_planet_id;
+use millennium_falcon::application_services::into_route_delays;
+use millennium_falcon::application_services::AutonomyOddsData;
+use millennium_falcon::application_services::CountdownAnalysisData;
 use millennium_falcon::application_services::EmpireData;
+use millennium_falcon::application_services::FleetPlanData;
 use millennium_falcon::application_services::MillenniumFalconData;
+use millennium_falcon::application_services::MissionPlanData;
+use millennium_falcon::application_services::RobustPlanData;
+use millennium_falcon::application_services::SensitivityReportData;
+use millennium_falcon::domain_models::RobustObjective;
+use millennium_falcon::domain_models::Scenario;
+use millennium_falcon::domain_services::adversary::place_bounty_hunters;
+use millennium_falcon::domain_services::analysis::compute_autonomy_analysis;
+use millennium_falcon::domain_services::analysis::compute_minimum_countdown;
+use millennium_falcon::domain_services::analysis::compute_sensitivity_report;
+use millennium_falcon::domain_services::compute_best_mission_plans;
+use millennium_falcon::domain_services::compute_pareto_frontier;
 use millennium_falcon::domain_services::compute_probability_of_success;
+use millennium_falcon::domain_services::fleet::compute_fleet_plan;
+use millennium_falcon::domain_services::robust::compute_robust_mission_plan;
+use millennium_falcon::domain_services::simulator::simulate_itinerary;
+use millennium_falcon::domain_services::stochastic::compute_stochastic_probability_of_success;
 use millennium_falcon::infrastructure_services::args::parse_cli;
+use millennium_falcon::infrastructure_services::args::Subcommand;
+use millennium_falcon::infrastructure_services::db::connect;
+use millennium_falcon::infrastructure_services::db::get_planets_from_db;
+use millennium_falcon::infrastructure_services::db::get_route_delays_from_db;
 use millennium_falcon::infrastructure_services::db::get_routes_from_db;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let (millennium_falcon_data_path, empire_data_path) = parse_cli()?;
-    let millennium_falcon_data = MillenniumFalconData::read(&millennium_falcon_data_path)?;
-    let empire_data = EmpireData::read(&empire_data_path)?;
-    let routes = get_routes_from_db(&millennium_falcon_data.routes_db).await?;
-    let (galaxy_routes, planet_ids) = into_galaxy_routes_and_planet_id(routes);
+    let cli_args = parse_cli()?;
+    let millennium_falcon_data = MillenniumFalconData::read(&cli_args.millennium_data_path)?;
+    let empire_data = EmpireData::read(&cli_args.empire_data_path)?;
+    let pool = connect(&millennium_falcon_data.routes_db).await?;
+    let routes = get_routes_from_db(&pool).await?;
+    let planets = get_planets_from_db(&pool).await?;
+    let (galaxy_routes, planet_ids) = into_galaxy_routes_and_planet_id(routes, planets);
     let hunter_planning = empire_data.to_bounty_hunters_planning(&planet_ids);
-    let proba = compute_probability_of_success(
-        &hunter_planning,
-        &galaxy_routes,
-        &planet_ids,
-        millennium_falcon_data.autonomy,
-        &millennium_falcon_data.departure,
-        &millennium_falcon_data.arrival,
-        empire_data.countdown,
-    )? * 100.;
-    println!("{proba}");
+    let mut mission = millennium_falcon_data.to_mission(empire_data.countdown);
+    let calendar = millennium_falcon_data.calendar();
+
+    match cli_args.subcommand {
+        Some(Subcommand::MinCountdown { target, limit }) => {
+            if let Some(limit) = limit {
+                mission.countdown = limit;
+            }
+            let analysis = CountdownAnalysisData::from(&compute_minimum_countdown(
+                &hunter_planning,
+                &galaxy_routes,
+                &planet_ids,
+                &mission,
+                target,
+            )?);
+            match analysis.minimum_countdown {
+                Some(countdown) => println!("minimum countdown: {countdown}"),
+                None => println!(
+                    "the odds don't reach {}% with a countdown up to {}",
+                    target * 100.,
+                    mission.countdown
+                ),
+            }
+            for countdown_odds in analysis.curve {
+                println!(
+                    "countdown {}: {}",
+                    countdown_odds.countdown,
+                    countdown_odds.odds * 100.
+                );
+            }
+        }
+
+        Some(Subcommand::Autonomy) => {
+            let analysis =
+                compute_autonomy_analysis(&hunter_planning, &galaxy_routes, &planet_ids, &mission)?;
+            if analysis.is_empty() {
+                println!("the mission is not feasible whatever the autonomy");
+            }
+            for autonomy_odds in analysis.iter().map(AutonomyOddsData::from) {
+                println!(
+                    "autonomy {}: {}",
+                    autonomy_odds.autonomy,
+                    autonomy_odds.odds * 100.
+                );
+            }
+        }
+
+        Some(Subcommand::Sensitivity) => {
+            let report = SensitivityReportData::from_sensitivity_report(
+                &compute_sensitivity_report(
+                    &hunter_planning,
+                    &galaxy_routes,
+                    &planet_ids,
+                    &mission,
+                )?,
+                &planet_ids,
+            );
+            println!("odds: {}", report.odds * 100.);
+            for sighting in report.sightings {
+                println!(
+                    "{} day {}: +{} (odds without it: {})",
+                    sighting.planet,
+                    sighting.day,
+                    sighting.impact * 100.,
+                    sighting.odds * 100.
+                );
+            }
+        }
+
+        Some(Subcommand::Stochastic) => {
+            let route_delays =
+                into_route_delays(get_route_delays_from_db(&pool).await?, &planet_ids)?;
+            let proba = compute_stochastic_probability_of_success(
+                &hunter_planning,
+                &galaxy_routes,
+                &route_delays,
+                &planet_ids,
+                &mission,
+            )?;
+            println!("{}", proba * 100.);
+        }
+
+        Some(Subcommand::Fleet) => {
+            let (names, missions): (Vec<_>, Vec<_>) = millennium_falcon_data
+                .to_fleet(empire_data.countdown)
+                .into_iter()
+                .unzip();
+            let fleet_plan = FleetPlanData::from_fleet_plan(
+                &compute_fleet_plan(&hunter_planning, &galaxy_routes, &planet_ids, &missions)?,
+                &names,
+                &planet_ids,
+            )
+            .with_dates(calendar.as_ref());
+            for ship in fleet_plan.ships.iter() {
+                println!("{}: {}", ship.name, ship.mission_plan.odds * 100.);
+                if cli_args.itinerary {
+                    print_itinerary(&ship.mission_plan);
+                }
+            }
+            println!("at least one ship: {}", fleet_plan.odds_at_least_one * 100.);
+            println!("all the ships: {}", fleet_plan.odds_all * 100.);
+        }
+
+        Some(Subcommand::Adversary { budget }) => {
+            let hunter_placement = place_bounty_hunters(
+                &hunter_planning,
+                &galaxy_routes,
+                &planet_ids,
+                &mission,
+                &empire_data.capture_model(&planet_ids),
+                budget,
+            )?;
+            // the Empire data goes to stdout to be saved, the odds to stderr
+            let optimal = if hunter_placement.optimal {
+                "optimal placement"
+            } else {
+                "best placement found"
+            };
+            eprintln!(
+                "odds with {} more bounty hunters: {} ({optimal})",
+                hunter_placement.hunters.len(),
+                hunter_placement.probability_of_success * 100.
+            );
+            println!(
+                "{}",
+                serde_json::to_string_pretty(
+                    &empire_data.with_bounty_hunters(&hunter_placement, &planet_ids)
+                )?
+            );
+        }
+
+        Some(Subcommand::Robust {
+            empire_data_paths,
+            weights,
+            worst_case,
+        }) => {
+            let mut scenarios = Vec::new();
+            for (i, empire_data_path) in empire_data_paths.iter().enumerate() {
+                let empire_data = EmpireData::read(empire_data_path)?;
+                scenarios.push(Scenario {
+                    hunter_planning: empire_data.to_bounty_hunters_planning(&planet_ids),
+                    countdown: empire_data.countdown,
+                    weight: weights.as_ref().map_or(1., |weights| weights[i]),
+                });
+            }
+            let objective = if worst_case {
+                RobustObjective::WorstCase
+            } else {
+                RobustObjective::Expected
+            };
+            let robust_plan = compute_robust_mission_plan(
+                &scenarios,
+                &galaxy_routes,
+                &planet_ids,
+                &mission,
+                objective,
+            )?;
+            let robust_plan =
+                RobustPlanData::from_robust_plan(&robust_plan, objective, &planet_ids)
+                    .with_dates(calendar.as_ref());
+            println!(
+                "expected odds: {}, worst-case odds: {}",
+                robust_plan.expected_odds * 100.,
+                robust_plan.worst_case_odds * 100.
+            );
+            for ((empire_data_path, odds), n_bounty_hunter) in empire_data_paths
+                .iter()
+                .zip(&robust_plan.scenario_odds)
+                .zip(&robust_plan.scenario_n_bounty_hunter)
+            {
+                println!(
+                    "{empire_data_path}: {} ({n_bounty_hunter} bounty hunters met)",
+                    odds * 100.
+                );
+            }
+            if cli_args.itinerary {
+                print_robust_itinerary(&robust_plan);
+            }
+        }
+
+        Some(Subcommand::Simulate { runs, seed }) => {
+            let mission_plan = compute_probability_of_success(
+                &hunter_planning,
+                &galaxy_routes,
+                &planet_ids,
+                &mission,
+            )?;
+            println!("odds: {}", mission_plan.probability_of_success * 100.);
+            let Some(itinerary) = &mission_plan.itinerary else {
+                println!("no itinerary reaches the arrival planet before the end of the countdown");
+                return Ok(());
+            };
+            let mut rng = match seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_entropy(),
+            };
+            let result =
+                simulate_itinerary(&hunter_planning, &galaxy_routes, itinerary, runs, &mut rng);
+            let (low, high) = result.confidence_interval;
+            println!(
+                "simulated odds: {} over {} runs (95% confidence interval: {} - {})",
+                result.success_rate * 100.,
+                result.n_runs,
+                low * 100.,
+                high * 100.
+            );
+            if cli_args.itinerary {
+                print_itinerary(
+                    &MissionPlanData::from_mission_plan(&mission_plan, &planet_ids)
+                        .with_dates(calendar.as_ref()),
+                );
+            }
+        }
+
+        None if cli_args.pareto || cli_args.top_k.is_some() => {
+            let mission_plans = match cli_args.top_k {
+                Some(k) => compute_best_mission_plans(
+                    &hunter_planning,
+                    &galaxy_routes,
+                    &planet_ids,
+                    &mission,
+                    k,
+                )?,
+                None => compute_pareto_frontier(
+                    &hunter_planning,
+                    &galaxy_routes,
+                    &planet_ids,
+                    &mission,
+                )?,
+            };
+            if mission_plans.is_empty() {
+                println!("0");
+            }
+            for mission_plan in mission_plans {
+                let mission_plan = MissionPlanData::from_mission_plan(&mission_plan, &planet_ids)
+                    .with_dates(calendar.as_ref());
+                // arrival_day and n_bounty_hunter are always defined for a feasible mission plan
+                println!(
+                    "{} (arrival day {}{}, {} bounty hunters)",
+                    mission_plan.odds * 100.,
+                    mission_plan.arrival_day.unwrap_or_default(),
+                    dated(&mission_plan.arrival_date),
+                    mission_plan.n_bounty_hunter.unwrap_or_default()
+                );
+                if cli_args.itinerary {
+                    print_itinerary(&mission_plan);
+                }
+            }
+        }
+        None => {
+            let mission_plan = compute_probability_of_success(
+                &hunter_planning,
+                &galaxy_routes,
+                &planet_ids,
+                &mission,
+            )?;
+            let proba = mission_plan.probability_of_success * 100.;
+            println!("{proba}");
+            if cli_args.itinerary {
+                print_itinerary(
+                    &MissionPlanData::from_mission_plan(&mission_plan, &planet_ids)
+                        .with_dates(calendar.as_ref()),
+                );
+            }
+        }
+    }
     Ok(())
 }
+
+fn print_itinerary(mission_plan: &MissionPlanData) {
+    let Some(itinerary) = &mission_plan.itinerary else {
+        println!("no itinerary reaches the arrival planet before the end of the countdown");
+        return;
+    };
+    for step in itinerary {
+        let bounty_hunter = if step.bounty_hunter {
+            ", bounty hunters!"
+        } else {
+            ""
+        };
+        println!(
+            "day {}{}: {} ({}, fuel: {}{bounty_hunter})",
+            step.day,
+            dated(&step.date),
+            step.planet,
+            step.action,
+            step.fuel
+        );
+    }
+}
+
+/// Print the steps of a robust plan, with the scenarios where bounty hunters are met, numbered from 1
+fn print_robust_itinerary(robust_plan: &RobustPlanData) {
+    let Some(itinerary) = &robust_plan.itinerary else {
+        println!("no itinerary reaches the arrival planet before the end of the countdown");
+        return;
+    };
+    for step in itinerary {
+        let scenarios = step
+            .bounty_hunters
+            .iter()
+            .enumerate()
+            .filter(|(_, met)| **met)
+            .map(|(i, _)| (i + 1).to_string())
+            .collect::<Vec<_>>();
+        let bounty_hunter = if scenarios.is_empty() {
+            String::new()
+        } else {
+            format!(", bounty hunters in scenarios {}!", scenarios.join(", "))
+        };
+        println!(
+            "day {}{}: {} ({}, fuel: {}{bounty_hunter})",
+            step.day,
+            dated(&step.date),
+            step.planet,
+            step.action,
+            step.fuel
+        );
+    }
+}
+
+/// Format an optional date to be displayed after a day
+fn dated(date: &Option<String>) -> String {
+    date.as_ref()
+        .map(|date| format!(" ({date})"))
+        .unwrap_or_default()
+}
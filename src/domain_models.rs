@@ -26,9 +26,10 @@ impl Display for PlanetId {
 }
 
 /// The `GalaxyRoutes` structure defines all interstellar routes within the galaxy.
-/// If there exists a route between planets with IDs `N` and `M`, and a Hyperspace jump between these planets take `D` days,
-/// then the vector at hash index `N` contains an entry `(M, D)`, and similarly,
-/// the vector at hash index `M` contains an entry `(N, D)`.
+/// If there exists a route from the planet with ID `N` to the planet with ID `M`, and a Hyperspace jump
+/// in this direction takes `D` days, then the vector at hash index `N` contains an entry `(M, D)`.
+/// Routes are directed: some lanes are one-way, or faster in one direction than in the other.
+/// Every planet reached by a route has an entry, possibly empty.
 /// This design facilitates quick access to all routes originating from a specific planet.
 /// It's important to note that planets are identified not by their names but by a `PlanetId`,
 /// ensuring that the representation of planets remains independent of their names.
@@ -53,15 +54,40 @@ impl GalaxyRoutes {
         GalaxyRoutes(HashMap::new())
     }
 
+    /// add a route that can be travelled in both directions with the same travel time
     pub fn add_route(&mut self, planet_id1: PlanetId, planet_id2: PlanetId, travel_time: u64) {
+        self.add_directed_route(planet_id1, planet_id2, travel_time);
+        self.add_directed_route(planet_id2, planet_id1, travel_time);
+    }
+
+    /// add a route that can only be travelled from `origin` to `destination`
+    pub fn add_directed_route(
+        &mut self,
+        origin: PlanetId,
+        destination: PlanetId,
+        travel_time: u64,
+    ) {
         self.0
-            .entry(planet_id1)
-            .or_default()
-            .push((planet_id2, travel_time));
-        self.0
-            .entry(planet_id2)
+            .entry(origin)
             .or_default()
-            .push((planet_id1, travel_time));
+            .push((destination, travel_time));
+        self.0.entry(destination).or_default();
+    }
+
+    /// Return the same galaxy with all the routes in the opposite direction
+    pub fn reversed(&self) -> GalaxyRoutes {
+        let mut reversed_routes = GalaxyRoutes(
+            self.0
+                .keys()
+                .map(|planet_id| (*planet_id, Vec::new()))
+                .collect(),
+        );
+        for (origin, routes) in self.0.iter() {
+            for (destination, travel_time) in routes {
+                reversed_routes.add_directed_route(*destination, *origin, *travel_time);
+            }
+        }
+        reversed_routes
     }
 
     /// create a GalaxyRoute object directly from a hashmap.
@@ -70,31 +96,97 @@ impl GalaxyRoutes {
     pub fn from_hashmap(
         galaxy_routes: HashMap<PlanetId, Vec<(PlanetId, u64)>>,
     ) -> Result<GalaxyRoutes> {
-        // check that if path from A to B exist, then path from B to A also exists.
+        // check that if path from A to B exist, then B is also in the galaxy.
         let origins = galaxy_routes.keys();
         for origin in origins {
-            for (destination, distance) in galaxy_routes.get(origin).unwrap().iter() {
+            for (destination, _) in galaxy_routes.get(origin).unwrap().iter() {
                 if !galaxy_routes.contains_key(destination) {
                     return Err(anyhow!(
                         "Route starting from {origin} to {destination} found, but no routes starting from {destination}"                    ));
                 }
-                if !galaxy_routes
-                    .get(destination)
-                    .unwrap()
-                    .contains(&(*origin, *distance))
-                {
-                    return Err(anyhow!("Route from {origin} to {destination} found, but no similar route from {destination} to {origin}"));
-                }
             }
         }
         Ok(GalaxyRoutes(galaxy_routes))
     }
 }
 
-/// Structure keeping the relationship between the planet id and its information (for now only name).
+/// Delays of the hyperspace jumps, taking longer than their travel time because of navigation errors.
+/// For each directed route that can be delayed, the possible delays in days with their probabilities.
+/// The remaining probability is the one of a jump on time, so without delays the travel times are deterministic.
+/// A delayed jump doesn't use more fuel than the travel time.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct RouteDelays(HashMap<(PlanetId, PlanetId), Vec<(u64, f64)>>);
+
+impl RouteDelays {
+    pub fn new() -> RouteDelays {
+        RouteDelays(HashMap::new())
+    }
+
+    /// add the probability for a jump from `origin` to `destination` to take `delay` more days.
+    /// Fail if the probabilities of the delays of the route sum to more than 1
+    pub fn add_delay(
+        &mut self,
+        origin: PlanetId,
+        destination: PlanetId,
+        delay: u64,
+        probability: f64,
+    ) -> Result<()> {
+        if delay == 0 {
+            return Err(anyhow!("a delay is at least 1 day"));
+        }
+        if !(0. ..=1.).contains(&probability) {
+            return Err(anyhow!(
+                "probability {probability} should be between 0 and 1"
+            ));
+        }
+        let delays = self.0.entry((origin, destination)).or_default();
+        if delays.iter().map(|(_, p)| p).sum::<f64>() + probability > 1. + f64::EPSILON {
+            return Err(anyhow!(
+                "the delays of the route from {origin} to {destination} have a probability above 1"
+            ));
+        }
+        delays.push((delay, probability));
+        Ok(())
+    }
+
+    /// true if no jump can be delayed
+    pub fn is_empty(&self) -> bool {
+        self.0.values().all(|delays| delays.is_empty())
+    }
+
+    /// possible travel times of a jump from `origin` to `destination`, with their probabilities
+    pub fn travel_times(
+        &self,
+        origin: &PlanetId,
+        destination: &PlanetId,
+        travel_time: u64,
+    ) -> Vec<(u64, f64)> {
+        let delays = self
+            .0
+            .get(&(*origin, *destination))
+            .map(|delays| delays.as_slice())
+            .unwrap_or_default();
+        let on_time = 1. - delays.iter().map(|(_, p)| p).sum::<f64>();
+        std::iter::once((travel_time, on_time.max(0.)))
+            .chain(
+                delays
+                    .iter()
+                    .map(|(delay, p)| (travel_time.saturating_add(*delay), *p)),
+            )
+            .filter(|(_, p)| *p > 0.)
+            .collect()
+    }
+}
+
+/// Structure keeping the relationship between the planet id and its information (name and fuel depot).
 /// This structure is the only owner of all planet data.
+/// When nothing is known about a planet, we consider that the Millennium Falcon can refuel there.
 #[derive(Debug, PartialEq, Eq, Clone)]
-pub struct PlanetCatalog(HashMap<String, PlanetId>);
+pub struct PlanetCatalog {
+    ids: HashMap<String, PlanetId>,
+    names: Vec<String>,
+    fuel_depots: Vec<bool>,
+}
 
 impl Default for PlanetCatalog {
     fn default() -> Self {
@@ -104,18 +196,37 @@ impl Default for PlanetCatalog {
 
 impl PlanetCatalog {
     pub fn get(&self, k: &str) -> Option<&PlanetId> {
-        self.0.get(k)
+        self.ids.get(k)
+    }
+
+    /// Return the name of a planet. As PlanetId are only created by the catalog, this should never fail
+    /// unless the PlanetId come from another catalog.
+    pub fn name(&self, planet_id: &PlanetId) -> Option<&str> {
+        self.names.get(planet_id.0).map(|name| name.as_str())
+    }
+
+    /// Return true if the Millennium Falcon can refuel on this planet
+    pub fn has_fuel_depot(&self, planet_id: &PlanetId) -> bool {
+        *self.fuel_depots.get(planet_id.0).unwrap_or(&true)
+    }
+
+    pub fn set_fuel_depot(&mut self, planet_id: &PlanetId, fuel_depot: bool) {
+        if let Some(v) = self.fuel_depots.get_mut(planet_id.0) {
+            *v = fuel_depot;
+        }
     }
 
     pub fn insert(&mut self, planet_name: String) -> Result<&PlanetId> {
-        if self.0.contains_key(&planet_name) {
+        if self.ids.contains_key(&planet_name) {
             return Err(anyhow!(
                 "Can't insert a planet that already exist in the map"
             ));
         }
-        let planet_id = PlanetId::new(self.0.len());
-        self.0.insert(planet_name.to_owned(), planet_id);
-        Ok(self.0.get(&planet_name).unwrap())
+        let planet_id = PlanetId::new(self.names.len());
+        self.names.push(planet_name.to_owned());
+        self.fuel_depots.push(true);
+        self.ids.insert(planet_name.to_owned(), planet_id);
+        Ok(self.ids.get(&planet_name).unwrap())
     }
 
     pub fn get_or_insert(&mut self, planet_name: String) -> PlanetId {
@@ -126,7 +237,11 @@ impl PlanetCatalog {
     }
 
     pub fn new() -> Self {
-        PlanetCatalog(HashMap::new())
+        PlanetCatalog {
+            ids: HashMap::new(),
+            names: Vec::new(),
+            fuel_depots: Vec::new(),
+        }
     }
 
     pub fn from_vec(planet_names: Vec<String>) -> Result<PlanetCatalog> {
@@ -138,22 +253,738 @@ impl PlanetCatalog {
     }
 }
 
-/// Structure that remember the days when bounty hunter are present on a planet
-#[derive(Debug, Eq, PartialEq)]
-pub struct BountyHunterPlanning(HashMap<PlanetId, HashSet<u64>>);
+/// Model giving the probability for the Millennium Falcon to be captured when meeting bounty hunters.
+/// Each encounter is independent: the Millennium Falcon can only be captured by bounty hunters
+/// if it escaped all the previous ones.
+pub trait CaptureModel {
+    /// Probability of being captured by the bounty hunters present on `planet` at `day`
+    fn capture_probability(&self, planet: &PlanetId, day: u64) -> f64;
+}
+
+/// Every encounter has the same probability to end with a capture, 10% by default.
+/// The probability of being captured after several encounters is then 1/10 + 9/100 + 81/1000 + ...
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct GeometricCaptureModel {
+    pub capture_probability: f64,
+}
+
+impl Default for GeometricCaptureModel {
+    fn default() -> Self {
+        GeometricCaptureModel {
+            capture_probability: 0.1,
+        }
+    }
+}
+
+impl CaptureModel for GeometricCaptureModel {
+    fn capture_probability(&self, _planet: &PlanetId, _day: u64) -> f64 {
+        self.capture_probability
+    }
+}
+
+/// Capture probabilities known for specific bounty hunters (identified by their planet and day) or for all the
+/// bounty hunters of a planet. The most specific probability is used, falling back to the geometric model.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct EmpireCaptureModel {
+    pub hunters: HashMap<(PlanetId, u64), f64>,
+    pub planets: HashMap<PlanetId, f64>,
+    pub default: GeometricCaptureModel,
+}
+
+impl EmpireCaptureModel {
+    /// Capture probability of the bounty hunters of the planet without specific information
+    pub fn planet_capture_probability(&self, planet: &PlanetId) -> f64 {
+        match self.planets.get(planet) {
+            Some(p) => *p,
+            None => self.default.capture_probability,
+        }
+    }
+}
+
+impl CaptureModel for EmpireCaptureModel {
+    fn capture_probability(&self, planet: &PlanetId, day: u64) -> f64 {
+        match self.hunters.get(&(*planet, day)) {
+            Some(p) => *p,
+            None => self.planet_capture_probability(planet),
+        }
+    }
+}
+
+/// Report of bounty hunters on a planet at a given day. As the intelligence of the Rebellion is not always right,
+/// the bounty hunters are only present with some probability.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Sighting {
+    pub presence_probability: f64,
+    /// probability of being captured when the bounty hunters are really there
+    pub capture_probability: f64,
+}
+
+impl Sighting {
+    /// Probability of being captured because of this sighting, whether the bounty hunters are there or not
+    pub fn expected_capture_probability(&self) -> f64 {
+        self.presence_probability * self.capture_probability
+    }
+}
+
+/// Bounty hunters the Millennium Falcon may meet on a planet a day
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Encounter {
+    /// bounty hunters of the planning, that may not be there
+    pub sighting: Option<Sighting>,
+    /// probability of being captured by the pursuers sent after the Millennium Falcon in the current hypothesis
+    pub pursuers_capture_probability: Option<f64>,
+    /// true if the encounter policy counts this day as an encounter with the bounty hunters there
+    pub counts: bool,
+    /// new value of `already_met`, if bounty hunters are there. It is reset when nobody is there
+    pub met_hunters: bool,
+    /// true if the bounty hunters send pursuers after the Millennium Falcon when they spot it
+    pub pursuit: bool,
+}
+
+/// What happens to the Millennium Falcon on a planet for some presences of the bounty hunters of the planning
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct EncounterOutcome {
+    /// probability of these presences
+    pub probability: f64,
+    /// probability of being captured, if the Millennium Falcon meets bounty hunters. They then spot it,
+    /// and send pursuers after it
+    pub capture_probability: Option<f64>,
+    /// new value of `already_met`
+    pub met_hunters: bool,
+}
+
+impl Encounter {
+    /// Outcomes of the encounter, depending on whether the bounty hunters of the planning are there.
+    /// Their presence is given by `presence` if it is known, else by the presence probability of the sighting.
+    /// Only the bounty hunters really there spot the Millennium Falcon, so the outcomes are merged only when
+    /// they have the same consequences, with the expected capture probability
+    pub fn outcomes(&self, presence: Option<bool>) -> Vec<EncounterOutcome> {
+        let presence_probability = match (self.sighting, presence) {
+            (None, _) | (_, Some(false)) => 0.,
+            (Some(_), Some(true)) => 1.,
+            (Some(sighting), None) => sighting.presence_probability,
+        };
+        let present = EncounterOutcome {
+            probability: presence_probability,
+            capture_probability: self.counts.then(|| {
+                self.sighting
+                    .map(|sighting| sighting.capture_probability)
+                    .into_iter()
+                    .chain(self.pursuers_capture_probability)
+                    .fold(0., f64::max)
+            }),
+            met_hunters: self.met_hunters,
+        };
+        // when nobody is there, there is nobody to meet again on the next days
+        let absent = EncounterOutcome {
+            probability: 1. - presence_probability,
+            capture_probability: self.pursuers_capture_probability.filter(|_| self.counts),
+            met_hunters: self.met_hunters && self.pursuers_capture_probability.is_some(),
+        };
+        if presence_probability >= 1. {
+            return vec![EncounterOutcome {
+                probability: 1.,
+                ..present
+            }];
+        }
+        if presence_probability <= 0. {
+            return vec![EncounterOutcome {
+                probability: 1.,
+                ..absent
+            }];
+        }
+        // without pursuit, being spotted has no consequence
+        if (!self.pursuit
+            || present.capture_probability.is_some() == absent.capture_probability.is_some())
+            && present.met_hunters == absent.met_hunters
+        {
+            let capture_probability =
+                match (present.capture_probability, absent.capture_probability) {
+                    (Some(present_capture), None) => Some(present.probability * present_capture),
+                    (None, Some(absent_capture)) => Some(absent.probability * absent_capture),
+                    (Some(present_capture), Some(absent_capture)) => Some(
+                        present.probability * present_capture + absent.probability * absent_capture,
+                    ),
+                    (None, None) => None,
+                };
+            return vec![EncounterOutcome {
+                probability: 1.,
+                capture_probability,
+                met_hunters: present.met_hunters,
+            }];
+        }
+        vec![present, absent]
+    }
+}
+
+/// Bounty hunters patrolling a planet every `period` days, from `first_day` until `last_day` (included) if defined.
+/// A patrol with a period of 1 covers a range of days.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Patrol {
+    pub first_day: u64,
+    pub last_day: Option<u64>,
+    pub period: u64,
+    pub sighting: Sighting,
+}
+
+impl Patrol {
+    /// Return true if the bounty hunters of the patrol are on the planet this day
+    pub fn is_present(&self, day: u64) -> bool {
+        day >= self.first_day
+            && self.last_day.is_none_or(|last_day| day <= last_day)
+            && (day - self.first_day).is_multiple_of(self.period)
+    }
+}
+
+/// Structure that remember the days when bounty hunter are present on a planet,
+/// and the probability to be captured by them.
+/// Patrols are not expanded day by day, so long patrols don't cost more than short ones.
+/// It also remembers the days when the blockades of the Empire close routes.
+#[derive(Debug, PartialEq, Default, Clone)]
+pub struct BountyHunterPlanning {
+    sightings: HashMap<PlanetId, HashMap<u64, Sighting>>,
+    patrols: HashMap<PlanetId, Vec<Patrol>>,
+    /// first and last days (included) of the closures of the routes, from origin to destination
+    route_closures: HashMap<(PlanetId, PlanetId), Vec<(u64, u64)>>,
+    /// if defined, bounty hunters go after the Millennium Falcon once it has been spotted
+    pursuit: Option<Pursuit>,
+    /// which days on a planet with bounty hunters count as an encounter
+    encounter_policy: EncounterPolicy,
+}
 
 impl BountyHunterPlanning {
-    /// Return 1 if meet with hunter, else 0
-    pub fn meet_with_hunter(&self, planet: &PlanetId, elapsed_time: &u64) -> u64 {
-        if let Some(v) = self.0.get(planet) {
-            if v.contains(elapsed_time) {
-                return 1;
-            }
+    /// Return the bounty hunters that may be on the planet this day. When several reports overlap,
+    /// the most reliable presence and the most dangerous capture probability are kept
+    pub fn sighting(&self, planet: &PlanetId, day: u64) -> Option<Sighting> {
+        let sighting = self
+            .sightings
+            .get(planet)
+            .and_then(|sightings| sightings.get(&day));
+        let patrols = self
+            .patrols
+            .get(planet)
+            .into_iter()
+            .flatten()
+            .filter(|patrol| patrol.is_present(day))
+            .map(|patrol| &patrol.sighting);
+        sighting
+            .into_iter()
+            .chain(patrols)
+            .copied()
+            .reduce(|a, b| Sighting {
+                presence_probability: a.presence_probability.max(b.presence_probability),
+                capture_probability: a.capture_probability.max(b.capture_probability),
+            })
+    }
+
+    /// Return the probability of being captured if bounty hunters may be on the planet this day, else None.
+    /// As the sightings are independent, this is the expectation over the presence of the bounty hunters
+    pub fn capture_probability(&self, planet: &PlanetId, day: u64) -> Option<f64> {
+        self.sighting(planet, day)
+            .map(|sighting| sighting.expected_capture_probability())
+    }
+
+    /// Last day when bounty hunters may be met, or None if there are no bounty hunters at all.
+    /// Patrols without end never leave, so only their first day counts: after the last day,
+    /// the bounty hunters met repeat every `patrol_cycle` days
+    pub fn last_day(&self) -> Option<u64> {
+        let sighting_days = self
+            .sightings
+            .values()
+            .flat_map(|days| days.keys().copied());
+        let patrol_days = self
+            .patrols
+            .values()
+            .flatten()
+            .map(|patrol| patrol.last_day.unwrap_or(patrol.first_day));
+        sighting_days.chain(patrol_days).max()
+    }
+
+    /// Number of days after which all the patrols without end are back, 1 if there are none.
+    /// Return None if it doesn't fit in a u64
+    pub fn patrol_cycle(&self) -> Option<u64> {
+        self.patrols
+            .values()
+            .flatten()
+            .filter(|patrol| patrol.last_day.is_none())
+            .try_fold(1u64, |cycle, patrol| {
+                let period = patrol.period.max(1);
+                let (mut a, mut b) = (cycle, period);
+                while b != 0 {
+                    (a, b) = (b, a % b);
+                }
+                (cycle / a).checked_mul(period)
+            })
+    }
+
+    /// Last day when a route is closed, or None if all routes are always open
+    pub fn last_closure_day(&self) -> Option<u64> {
+        self.route_closures
+            .values()
+            .flatten()
+            .map(|(_, last_day)| *last_day)
+            .max()
+    }
+
+    /// Return true if the route between the two planets can't be taken this day
+    pub fn is_route_closed(&self, origin: &PlanetId, destination: &PlanetId, day: u64) -> bool {
+        self.route_closures
+            .get(&(*origin, *destination))
+            .is_some_and(|closures| {
+                closures
+                    .iter()
+                    .any(|(first_day, last_day)| (*first_day..=*last_day).contains(&day))
+            })
+    }
+
+    /// Close the route from `origin` to `destination` from `first_day` to `last_day` included.
+    /// The route from `destination` to `origin` stays open
+    pub fn add_route_closure(
+        &mut self,
+        origin: PlanetId,
+        destination: PlanetId,
+        first_day: u64,
+        last_day: u64,
+    ) {
+        self.route_closures
+            .entry((origin, destination))
+            .or_default()
+            .push((first_day, last_day));
+    }
+
+    /// Planets and days of the sightings reported day by day, sorted. Patrols are not included
+    pub fn sighting_days(&self) -> Vec<(PlanetId, u64)> {
+        let mut sighting_days = self
+            .sightings
+            .iter()
+            .flat_map(|(planet, days)| days.keys().map(|day| (*planet, *day)))
+            .collect::<Vec<_>>();
+        sighting_days.sort_unstable();
+        sighting_days
+    }
+
+    /// Remove the sighting reported on the planet this day, and return it
+    pub fn remove_sighting(&mut self, planet: &PlanetId, day: u64) -> Option<Sighting> {
+        self.sightings
+            .get_mut(planet)
+            .and_then(|sightings| sightings.remove(&day))
+    }
+
+    /// Report bounty hunters on the planet this day, replacing the previous report if any
+    pub fn insert_sighting(&mut self, planet: PlanetId, day: u64, sighting: Sighting) {
+        self.sightings
+            .entry(planet)
+            .or_default()
+            .insert(day, sighting);
+    }
+
+    /// Send bounty hunters after the Millennium Falcon each time it is spotted
+    pub fn set_pursuit(&mut self, pursuit: Pursuit) {
+        self.pursuit = Some(pursuit);
+    }
+
+    pub fn pursuit(&self) -> Option<&Pursuit> {
+        self.pursuit.as_ref()
+    }
+
+    pub fn set_encounter_policy(&mut self, encounter_policy: EncounterPolicy) {
+        self.encounter_policy = encounter_policy;
+    }
+
+    pub fn encounter_policy(&self) -> &EncounterPolicy {
+        &self.encounter_policy
+    }
+
+    /// Apply the encounter policy to the Millennium Falcon being on the planet this day after `action`.
+    /// `already_met` is true if the Millennium Falcon stayed on the planet since it met the bounty hunters there,
+    /// and the bounty hunters never left. `pursuers` are the planets and days where the pursuers sent after
+    /// the Millennium Falcon arrive.
+    /// Return None if no bounty hunters may be on the planet this day
+    pub fn encounter(
+        &self,
+        planet: &PlanetId,
+        day: u64,
+        action: Action,
+        pursuers: &[(PlanetId, u64)],
+        already_met: bool,
+    ) -> Option<Encounter> {
+        let sighting = self.sighting(planet, day);
+        let pursuers_capture_probability = self
+            .pursuit
+            .filter(|_| pursuers.contains(&(*planet, day)))
+            .map(|pursuit| pursuit.capture_probability);
+        if sighting.is_none() && pursuers_capture_probability.is_none() {
+            return None;
         }
-        0
+        let staying = matches!(action, Action::Wait | Action::Refuel);
+        if staying && already_met {
+            // already_met is only set when consecutive days count once
+            return Some(Encounter {
+                sighting,
+                pursuers_capture_probability,
+                counts: false,
+                met_hunters: true,
+                pursuit: self.pursuit.is_some(),
+            });
+        }
+        let counts = match self.encounter_policy.days {
+            EncounterDays::Arrival => !staying,
+            EncounterDays::Present => action != Action::Refuel,
+            EncounterDays::PresentAndRefuel => true,
+        };
+        Some(Encounter {
+            sighting,
+            pursuers_capture_probability,
+            counts,
+            // a day that doesn't count doesn't end the encounter of the previous days
+            met_hunters: if counts {
+                self.encounter_policy.consecutive_days_once
+            } else {
+                already_met
+            },
+            pursuit: self.pursuit.is_some(),
+        })
+    }
+
+    /// Add bounty hunters patrolling the planet
+    pub fn add_patrol(&mut self, planet: PlanetId, patrol: Patrol) {
+        self.patrols.entry(planet).or_default().push(patrol);
     }
 
+    /// Create a planning where the capture probabilities follow the default geometric model
     pub fn new(planet_to_days: HashMap<PlanetId, HashSet<u64>>) -> BountyHunterPlanning {
-        BountyHunterPlanning(planet_to_days)
+        BountyHunterPlanning::with_capture_model(planet_to_days, &GeometricCaptureModel::default())
+    }
+
+    /// Create a planning where the bounty hunters are certainly present
+    pub fn with_capture_model(
+        planet_to_days: HashMap<PlanetId, HashSet<u64>>,
+        capture_model: &dyn CaptureModel,
+    ) -> BountyHunterPlanning {
+        BountyHunterPlanning::with_presence_probabilities(
+            planet_to_days
+                .into_iter()
+                .map(|(planet, days)| (planet, days.into_iter().map(|day| (day, 1.)).collect()))
+                .collect(),
+            capture_model,
+        )
+    }
+
+    /// Create a planning from the days when bounty hunters may be present on a planet, with their presence probability
+    pub fn with_presence_probabilities(
+        planet_to_days: HashMap<PlanetId, HashMap<u64, f64>>,
+        capture_model: &dyn CaptureModel,
+    ) -> BountyHunterPlanning {
+        let sightings = planet_to_days
+            .into_iter()
+            .map(|(planet, days)| {
+                let sightings = days
+                    .into_iter()
+                    .map(|(day, presence_probability)| {
+                        let sighting = Sighting {
+                            presence_probability,
+                            capture_probability: capture_model.capture_probability(&planet, day),
+                        };
+                        (day, sighting)
+                    })
+                    .collect();
+                (planet, sightings)
+            })
+            .collect();
+        BountyHunterPlanning {
+            sightings,
+            patrols: HashMap::new(),
+            route_closures: HashMap::new(),
+            pursuit: None,
+            encounter_policy: EncounterPolicy::default(),
+        }
+    }
+}
+
+/// Bounty hunters converging on the Millennium Falcon: each time it meets bounty hunters on a planet, they call
+/// for help, and bounty hunters reach every neighbouring planet after the travel time of the route from this planet.
+/// The Millennium Falcon is considered spotted even when the bounty hunters were only possibly there.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Pursuit {
+    /// probability of being captured by the bounty hunters of the pursuit
+    pub capture_probability: f64,
+}
+
+impl Pursuit {
+    /// Planets and days where the pursuers arrive after the Millennium Falcon is spotted on the planet this day
+    pub fn pursuers<'a>(
+        &self,
+        galaxy_routes: &'a GalaxyRoutes,
+        planet: &PlanetId,
+        day: u64,
+    ) -> impl Iterator<Item = (PlanetId, u64)> + 'a {
+        galaxy_routes
+            .get(planet)
+            .into_iter()
+            .flatten()
+            .map(move |(neighbour, travel_time)| (*neighbour, day.saturating_add(*travel_time)))
     }
 }
+
+/// Days on a planet with bounty hunters where the Millennium Falcon meets them
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum EncounterDays {
+    /// only the day of the arrival on the planet, or of the departure of the mission
+    Arrival,
+    /// every day on the planet, except the days spent refuelling, hidden in the fuel depot
+    Present,
+    /// every day on the planet
+    #[default]
+    PresentAndRefuel,
+}
+
+/// Rules deciding when the Millennium Falcon meets the bounty hunters of the planning
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct EncounterPolicy {
+    pub days: EncounterDays,
+    /// if true, bounty hunters present on a planet for several consecutive days are only met once
+    /// while the Millennium Falcon stays there
+    pub consecutive_days_once: bool,
+}
+
+/// How the Millennium Falcon refuels on a planet with a fuel depot
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum RefuelModel {
+    /// The tank is filled to autonomy after `days` consecutive days of refuelling.
+    /// Leaving the planet before the end of the refuel doesn't give any fuel.
+    Full { days: u64 },
+    /// Each day of refuelling adds `fuel_per_day` to the tank, up to autonomy
+    Partial { fuel_per_day: u64 },
+}
+
+impl Default for RefuelModel {
+    fn default() -> Self {
+        RefuelModel::Full { days: 1 }
+    }
+}
+
+impl RefuelModel {
+    /// Return the fuel and the number of days spent refuelling after one more day of refuel
+    pub fn refuel(&self, fuel: u64, refuelling: u64, autonomy: u64) -> (u64, u64) {
+        match self {
+            RefuelModel::Full { days } if refuelling + 1 >= *days => (autonomy, 0),
+            RefuelModel::Full { .. } => (fuel, refuelling + 1),
+            RefuelModel::Partial { fuel_per_day } => {
+                (autonomy.min(fuel.saturating_add(*fuel_per_day)), 0)
+            }
+        }
+    }
+}
+
+/// Planet the Millennium Falcon has to visit before reaching the arrival planet, by the `deadline` day if defined
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Waypoint {
+    pub planet: String,
+    pub deadline: Option<u64>,
+}
+
+/// Planet the Millennium Falcon can start from, with a full tank, on `start_day`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Departure {
+    pub planet: String,
+    pub start_day: u64,
+}
+
+/// Parameters of the mission of the Millennium Falcon: where it can start, where it can go,
+/// how far it can jump and how many days it has to do so.
+/// The Millennium Falcon starts from one of the departures and reaches one of the arrivals, the best combination is chosen.
+/// On the way, it can't land on the forbidden planets and has to visit all the waypoints,
+/// in the order of the list if `ordered_waypoints` is true.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Mission {
+    pub autonomy: u64,
+    pub departures: Vec<Departure>,
+    pub arrivals: Vec<String>,
+    pub countdown: u64,
+    pub refuel_model: RefuelModel,
+    pub forbidden_planets: Vec<String>,
+    pub waypoints: Vec<Waypoint>,
+    pub ordered_waypoints: bool,
+}
+
+/// How the Millennium Falcon reached a step of its itinerary
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Action {
+    /// The Millennium Falcon is on its departure planet, at the beginning of the mission
+    Departure,
+    /// The Millennium Falcon arrived on the planet after a hyperspace jump
+    Travel,
+    /// The Millennium Falcon stayed one day on the planet to refuel.
+    /// When a full refuel takes several days, the fuel is only updated on the last one
+    Refuel,
+    /// The Millennium Falcon stayed one day on the planet, without refuelling
+    Wait,
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let action = match self {
+            Action::Departure => "departure",
+            Action::Travel => "travel",
+            Action::Refuel => "refuel",
+            Action::Wait => "wait",
+        };
+        write!(f, "{action}")
+    }
+}
+
+/// Position of the Millennium Falcon at a given day of its itinerary
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Step {
+    pub planet: PlanetId,
+    pub day: u64,
+    /// fuel left in the tank at the end of the step
+    pub fuel: u64,
+    pub action: Action,
+    /// true if the Millennium Falcon meet bounty hunters during this step
+    pub bounty_hunter: bool,
+}
+
+/// Day-by-day plan followed by the Millennium Falcon, from its departure to its arrival.
+/// Only the days where something happens are listed: the days spent in hyperspace are implicit.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Itinerary(Vec<Step>);
+
+impl Itinerary {
+    pub fn new(steps: Vec<Step>) -> Itinerary {
+        Itinerary(steps)
+    }
+
+    pub fn steps(&self) -> &[Step] {
+        &self.0
+    }
+
+    pub fn arrival_day(&self) -> Option<u64> {
+        self.0.last().map(|step| step.day)
+    }
+
+    pub fn departure_planet(&self) -> Option<&PlanetId> {
+        self.0.first().map(|step| &step.planet)
+    }
+
+    pub fn arrival_planet(&self) -> Option<&PlanetId> {
+        self.0.last().map(|step| &step.planet)
+    }
+
+    pub fn n_bounty_hunter(&self) -> u64 {
+        self.0.iter().filter(|step| step.bounty_hunter).count() as u64
+    }
+}
+
+/// Output of the mission computation: the probability of success,
+/// and the itinerary to follow to reach it if the mission is feasible
+#[derive(Debug, PartialEq, Clone)]
+pub struct MissionPlan {
+    pub probability_of_success: f64,
+    pub itinerary: Option<Itinerary>,
+}
+
+/// Best odds of the mission when the countdown is `countdown` days
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct CountdownOdds {
+    pub countdown: u64,
+    pub probability_of_success: f64,
+}
+
+/// Odds of the mission depending on the countdown, and smallest countdown reaching a target probability of success.
+/// The curve only contains the countdowns where the odds improve: for a countdown between two points of the curve,
+/// the odds are the ones of the previous point, and 0 before the first point.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CountdownAnalysis {
+    pub curve: Vec<CountdownOdds>,
+    pub minimum_countdown: Option<u64>,
+}
+
+/// Best odds of the mission when the Millennium Falcon has an autonomy of `autonomy` days
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct AutonomyOdds {
+    pub autonomy: u64,
+    pub probability_of_success: f64,
+}
+
+/// Odds of the mission if a sighting turned out to be wrong, and how much they improve
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct SightingImpact {
+    pub planet: PlanetId,
+    pub day: u64,
+    /// probability of success without this sighting
+    pub probability_of_success: f64,
+    /// improvement of the probability of success when removing this sighting
+    pub impact: f64,
+}
+
+/// Odds of the mission, and the sightings ranked by decreasing impact on them
+#[derive(Debug, PartialEq, Clone)]
+pub struct SensitivityReport {
+    pub probability_of_success: f64,
+    pub sightings: Vec<SightingImpact>,
+}
+
+/// Bounty hunters the Empire adds to its planning to minimize the odds of the mission
+#[derive(Debug, PartialEq, Clone)]
+pub struct HunterPlacement {
+    /// planets and days of the added bounty hunters
+    pub hunters: Vec<(PlanetId, u64)>,
+    /// probability of success of the mission with the added bounty hunters
+    pub probability_of_success: f64,
+    /// true if no other placement within the budget gives lower odds
+    pub optimal: bool,
+}
+
+/// Possible plan of the Empire, with its likelihood relative to the other scenarios
+#[derive(Debug, PartialEq, Clone)]
+pub struct Scenario {
+    pub hunter_planning: BountyHunterPlanning,
+    pub countdown: u64,
+    pub weight: f64,
+}
+
+/// Odds to maximize when the plan of the Empire is uncertain
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum RobustObjective {
+    /// odds averaged over the scenarios, according to their weights
+    #[default]
+    Expected,
+    /// odds in the least favorable scenario
+    WorstCase,
+}
+
+/// Single itinerary followed whatever the scenario, with its odds in each of them.
+/// The steps of the itinerary are marked with bounty hunters when they are met in at least one scenario
+#[derive(Debug, PartialEq, Clone)]
+pub struct RobustPlan {
+    pub itinerary: Option<Itinerary>,
+    /// for each scenario, true at the steps of the itinerary where bounty hunters are met in this scenario
+    pub bounty_hunters: Vec<Vec<bool>>,
+    /// probability of success in each scenario, in the order of the scenarios
+    pub probabilities_of_success: Vec<f64>,
+    pub expected_probability_of_success: f64,
+    pub worst_case_probability_of_success: f64,
+}
+
+/// Empirical success rate of the Millennium Falcon over simulated missions
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct SimulationResult {
+    pub n_runs: u64,
+    pub n_successes: u64,
+    pub success_rate: f64,
+    /// 95% confidence interval of the probability of success
+    pub confidence_interval: (f64, f64),
+}
+
+/// Mission plans of the ships of a fleet, each one following its own best itinerary.
+/// The captures of different ships are independent once the presence of the bounty hunters is known
+#[derive(Debug, PartialEq, Clone)]
+pub struct FleetPlan {
+    /// mission plan of each ship, in the order of the missions
+    pub mission_plans: Vec<MissionPlan>,
+    /// probability that at least one ship reaches its arrival planet
+    pub probability_at_least_one: f64,
+    /// probability that all the ships reach their arrival planet
+    pub probability_all: f64,
+}
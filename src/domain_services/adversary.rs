@@ -0,0 +1,338 @@
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+use anyhow::{anyhow, Result};
+use itertools::Itertools;
+
+use crate::domain_models::{
+    BountyHunterPlanning, CaptureModel, GalaxyRoutes, HunterPlacement, Mission, MissionPlan,
+    PlanetCatalog, PlanetId, Sighting,
+};
+
+use super::{compute_all_time_to_destination, compute_probability_of_success, SearchContext};
+
+/// Largest number of placements evaluated to find the optimal one. Above, a local search is used
+const MAX_EXACT_EVALUATIONS: usize = 1000;
+
+/// Largest number of bounty hunters the Empire can add
+pub const MAX_BUDGET: usize = 100;
+
+/// Find where the Empire should send `budget` more bounty hunters, each one for a single day, to minimize the odds
+/// of the mission. The new bounty hunters are certainly present, and capture the Millennium Falcon with the probability
+/// of `capture_model`. Planets and days already covered by `hunter_planning` are not considered.
+///
+/// When there are few possible placements, all of them are evaluated. Otherwise, the bounty hunters are placed one
+/// by one on the itinerary the Millennium Falcon would follow, as bounty hunters elsewhere can't change the odds,
+/// then moved one at a time while it reduces the odds. The budget can't be above `MAX_BUDGET`.
+pub fn place_bounty_hunters(
+    hunter_planning: &BountyHunterPlanning,
+    galaxy_routes: &GalaxyRoutes,
+    planet_id_map: &PlanetCatalog,
+    mission: &Mission,
+    capture_model: &dyn CaptureModel,
+    budget: usize,
+) -> Result<HunterPlacement> {
+    place_bounty_hunters_with_limit(
+        hunter_planning,
+        galaxy_routes,
+        planet_id_map,
+        mission,
+        capture_model,
+        budget,
+        MAX_EXACT_EVALUATIONS,
+    )
+}
+
+/// Same as `place_bounty_hunters`, evaluating all the placements only if there are at most `max_exact_evaluations` of them
+fn place_bounty_hunters_with_limit(
+    hunter_planning: &BountyHunterPlanning,
+    galaxy_routes: &GalaxyRoutes,
+    planet_id_map: &PlanetCatalog,
+    mission: &Mission,
+    capture_model: &dyn CaptureModel,
+    budget: usize,
+    max_exact_evaluations: usize,
+) -> Result<HunterPlacement> {
+    let evaluate = |hunters: &[(PlanetId, u64)]| -> Result<MissionPlan> {
+        let mut planning = hunter_planning.clone();
+        for (planet, day) in hunters {
+            let sighting = Sighting {
+                presence_probability: 1.,
+                capture_probability: capture_model.capture_probability(planet, *day),
+            };
+            planning.insert_sighting(*planet, *day, sighting);
+        }
+        compute_probability_of_success(&planning, galaxy_routes, planet_id_map, mission)
+    };
+
+    if budget > MAX_BUDGET {
+        return Err(anyhow!(
+            "the Empire can't add more than {MAX_BUDGET} bounty hunters"
+        ));
+    }
+    let candidate_days = candidate_days(hunter_planning, galaxy_routes, planet_id_map, mission)?;
+    let is_candidate = |(planet, day): &(PlanetId, u64)| {
+        candidate_days
+            .get(planet)
+            .is_some_and(|days| days.contains(day))
+            && hunter_planning.sighting(planet, *day).is_none()
+    };
+    // with more candidates, C(n, budget) >= n is above the number of placements evaluated, unless the budget is 0,
+    // so the candidates are listed only up to this limit, whatever the countdown
+    let candidates = candidate_days
+        .iter()
+        .flat_map(|(planet, days)| days.clone().map(|day| (*planet, day)))
+        .filter(is_candidate)
+        .take(budget + max_exact_evaluations.max(1) + 1)
+        .collect::<Vec<_>>();
+    if candidates.len() <= budget {
+        // more bounty hunters can't increase the odds, so the best is to use all the places
+        let mission_plan = evaluate(&candidates)?;
+        return Ok(HunterPlacement {
+            hunters: candidates,
+            probability_of_success: mission_plan.probability_of_success,
+            optimal: true,
+        });
+    }
+
+    // a single placement is always evaluated
+    if n_combinations(candidates.len(), budget) <= max_exact_evaluations.max(1) {
+        let mut best: Option<HunterPlacement> = None;
+        for hunters in candidates.into_iter().combinations(budget) {
+            let probability_of_success = evaluate(&hunters)?.probability_of_success;
+            if best
+                .as_ref()
+                .is_none_or(|best| probability_of_success < best.probability_of_success)
+            {
+                best = Some(HunterPlacement {
+                    hunters,
+                    probability_of_success,
+                    optimal: true,
+                });
+            }
+        }
+        // there is at least one combination, as there are more candidates than the budget
+        return best.ok_or(anyhow!("no placement of the bounty hunters"));
+    }
+
+    // the Millennium Falcon only meets the bounty hunters on the days of its itinerary, so bounty hunters elsewhere
+    // leave its odds unchanged
+    let on_itinerary = |mission_plan: &MissionPlan, hunters: &[(PlanetId, u64)]| {
+        mission_plan
+            .itinerary
+            .iter()
+            .flat_map(|itinerary| itinerary.steps())
+            .map(|step| (step.planet, step.day))
+            .filter(|hunter| is_candidate(hunter) && !hunters.contains(hunter))
+            .unique()
+            .collect::<Vec<_>>()
+    };
+    // best hunter to add to `hunters`, with the mission plan it leaves to the Millennium Falcon
+    let best_addition = |hunters: &mut Vec<(PlanetId, u64)>,
+                         mission_plan: &MissionPlan|
+     -> Result<Option<((PlanetId, u64), MissionPlan)>> {
+        let mut best: Option<((PlanetId, u64), MissionPlan)> = None;
+        for hunter in on_itinerary(mission_plan, hunters) {
+            hunters.push(hunter);
+            let new_mission_plan = evaluate(hunters)?;
+            hunters.pop();
+            if best.as_ref().is_none_or(|(_, best)| {
+                new_mission_plan.probability_of_success < best.probability_of_success
+            }) {
+                best = Some((hunter, new_mission_plan));
+            }
+        }
+        Ok(best)
+    };
+
+    // greedy placement
+    let mut hunters = Vec::new();
+    let mut mission_plan = evaluate(&hunters)?;
+    while hunters.len() < budget {
+        match best_addition(&mut hunters, &mission_plan)? {
+            Some((hunter, new_mission_plan)) => {
+                hunters.push(hunter);
+                mission_plan = new_mission_plan;
+            }
+            // no itinerary anymore, or all its days are already covered
+            None => break,
+        }
+    }
+
+    // local search: move a bounty hunter to the itinerary the Millennium Falcon would follow without it
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for i in 0..hunters.len() {
+            let removed = hunters.remove(i);
+            let mission_plan_without = evaluate(&hunters)?;
+            match best_addition(&mut hunters, &mission_plan_without)? {
+                Some((hunter, new_mission_plan))
+                    if new_mission_plan.probability_of_success
+                        < mission_plan.probability_of_success =>
+                {
+                    hunters.insert(i, hunter);
+                    mission_plan = new_mission_plan;
+                    improved = true;
+                }
+                _ => hunters.insert(i, removed),
+            }
+        }
+    }
+
+    Ok(HunterPlacement {
+        hunters,
+        probability_of_success: mission_plan.probability_of_success,
+        // the Millennium Falcon can't do worse than not reaching its destination
+        optimal: mission_plan.probability_of_success == 0.,
+    })
+}
+
+/// Days where the Millennium Falcon may be on each planet during the mission. The candidate bounty hunters are the planets
+/// and days among them where no bounty hunters are planned yet
+fn candidate_days(
+    hunter_planning: &BountyHunterPlanning,
+    galaxy_routes: &GalaxyRoutes,
+    planet_id_map: &PlanetCatalog,
+    mission: &Mission,
+) -> Result<BTreeMap<PlanetId, RangeInclusive<u64>>> {
+    let Some(search_context) =
+        SearchContext::new(hunter_planning, galaxy_routes, planet_id_map, mission)?
+    else {
+        return Ok(BTreeMap::new());
+    };
+    let departure_ids = search_context
+        .departures
+        .iter()
+        .map(|(planet, _)| *planet)
+        .collect();
+    let first_day = search_context
+        .departures
+        .iter()
+        .map(|(_, start_day)| *start_day)
+        .min()
+        .unwrap_or_default();
+    // the time from the departures is the time to the departures in the reversed galaxy
+    let time_from_departure =
+        compute_all_time_to_destination(&galaxy_routes.reversed(), &departure_ids)?;
+
+    let mut candidate_days = BTreeMap::new();
+    for (planet, time_from_departure) in time_from_departure {
+        if search_context.forbidden_planets.contains(&planet) {
+            continue;
+        }
+        let Some(time_to_destination) = search_context.all_time_to_destination.get(&planet) else {
+            continue;
+        };
+        let Some(last_day) = mission.countdown.checked_sub(*time_to_destination) else {
+            continue;
+        };
+        candidate_days.insert(
+            planet,
+            first_day.saturating_add(time_from_departure)..=last_day,
+        );
+    }
+    Ok(candidate_days)
+}
+
+/// Number of ways to choose `k` elements among `n`, saturating at usize::MAX
+fn n_combinations(n: usize, k: usize) -> usize {
+    let k = k.min(n - k);
+    let mut result: usize = 1;
+    for i in 0..k {
+        // result * (n - i) is divisible by i + 1, as it is C(n, i) * (n - i)
+        result = match result.checked_mul(n - i) {
+            Some(v) => v / (i + 1),
+            None => return usize::MAX,
+        };
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        domain_models::GeometricCaptureModel,
+        domain_services::test::{get_example_mission, get_example_universe},
+    };
+
+    use super::{
+        n_combinations, place_bounty_hunters, place_bounty_hunters_with_limit, MAX_BUDGET,
+    };
+
+    #[test]
+    fn test_n_combinations() {
+        assert_eq!(n_combinations(12, 1), 12);
+        assert_eq!(n_combinations(12, 3), 220);
+        assert_eq!(n_combinations(12, 12), 1);
+        assert_eq!(n_combinations(1000, 500), usize::MAX);
+    }
+
+    #[test]
+    fn test_place_bounty_hunters() {
+        let (planet_id_map, galaxy_routes, hunter_planning) = get_example_universe();
+        let mission = get_example_mission(10);
+        let capture_model = GeometricCaptureModel::default();
+
+        // the Empire can only add bounty hunters on the days the Millennium Falcon can't avoid:
+        // its departure and its arrival, or the planets on the way
+        for (budget, probability_of_success) in [(0, 1.), (1, 0.9), (2, 0.81), (3, 0.729)] {
+            let exact = place_bounty_hunters(
+                &hunter_planning,
+                &galaxy_routes,
+                &planet_id_map,
+                &mission,
+                &capture_model,
+                budget,
+            )
+            .unwrap();
+            assert_eq!(exact.hunters.len(), budget);
+            assert_eq!(exact.probability_of_success, probability_of_success);
+            assert!(exact.optimal);
+            let local_search = place_bounty_hunters_with_limit(
+                &hunter_planning,
+                &galaxy_routes,
+                &planet_id_map,
+                &mission,
+                &capture_model,
+                budget,
+                0,
+            )
+            .unwrap();
+            // the local search finds a placement as good on this small galaxy
+            assert_eq!(local_search.hunters.len(), budget);
+            assert_eq!(local_search.probability_of_success, probability_of_success);
+        }
+    }
+    #[test]
+    fn test_place_bounty_hunters_with_large_countdown() {
+        let (planet_id_map, galaxy_routes, hunter_planning) = get_example_universe();
+        let mission = get_example_mission(1_000_000_000);
+        let capture_model = GeometricCaptureModel::default();
+
+        // the Millennium Falcon can wait anywhere for a long time, so a single bounty hunter can only wait for it
+        // at its departure. The candidates are not all listed
+        let hunter_placement = place_bounty_hunters(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &mission,
+            &capture_model,
+            1,
+        )
+        .unwrap();
+        let tatooine = *planet_id_map.get("Tatooine").unwrap();
+        assert_eq!(hunter_placement.hunters, [(tatooine, 0)]);
+        assert_eq!(hunter_placement.probability_of_success, 0.9);
+
+        assert!(place_bounty_hunters(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &mission,
+            &capture_model,
+            MAX_BUDGET + 1,
+        )
+        .is_err());
+    }
+}
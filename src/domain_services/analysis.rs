@@ -0,0 +1,417 @@
+use anyhow::Result;
+
+use std::collections::{BTreeMap, HashMap};
+
+use itertools::Itertools;
+
+use crate::domain_models::{
+    AutonomyOdds, BountyHunterPlanning, CountdownAnalysis, CountdownOdds, GalaxyRoutes, Mission,
+    PlanetCatalog, SensitivityReport, SightingImpact,
+};
+
+use super::{compute_pareto_frontier, compute_probability_of_success, SearchContext, SearchMode};
+
+/// Compute the odds of the mission for every countdown up to the countdown of the mission,
+/// and the smallest countdown where the probability of success reaches `target`.
+/// The best odds for a countdown are the ones of the safest itinerary arriving before its end,
+/// so a single search of the Pareto frontier of the arrival day versus the odds answers for all the countdowns.
+pub fn compute_minimum_countdown(
+    hunter_planning: &BountyHunterPlanning,
+    galaxy_routes: &GalaxyRoutes,
+    planet_id_map: &PlanetCatalog,
+    mission: &Mission,
+    target: f64,
+) -> Result<CountdownAnalysis> {
+    let pareto_frontier =
+        compute_pareto_frontier(hunter_planning, galaxy_routes, planet_id_map, mission)?;
+
+    // the frontier is sorted by decreasing arrival day
+    let mut curve: Vec<CountdownOdds> = Vec::new();
+    for mission_plan in pareto_frontier.iter().rev() {
+        let Some(countdown) = mission_plan
+            .itinerary
+            .as_ref()
+            .and_then(|itinerary| itinerary.arrival_day())
+        else {
+            continue;
+        };
+        if curve.last().is_some_and(|countdown_odds| {
+            countdown_odds.probability_of_success >= mission_plan.probability_of_success
+        }) {
+            continue;
+        }
+        curve.push(CountdownOdds {
+            countdown,
+            probability_of_success: mission_plan.probability_of_success,
+        });
+    }
+
+    let minimum_countdown = curve
+        .iter()
+        .find(|countdown_odds| countdown_odds.probability_of_success >= target)
+        .map(|countdown_odds| countdown_odds.countdown);
+    Ok(CountdownAnalysis {
+        curve,
+        minimum_countdown,
+    })
+}
+
+/// Compute the odds of the mission depending on the autonomy of the Millennium Falcon, from the smallest autonomy
+/// where the mission is feasible to the autonomy where the odds stop improving.
+/// As for the countdown analysis, the curve only contains the autonomies where the odds improve, and is empty
+/// if the mission is not feasible whatever the autonomy.
+///
+/// More fuel never removes options, so the odds increase with the autonomy, and an autonomy larger than the countdown
+/// is useless. This allows to find the feasibility threshold by bisection, and the points of the curve by splitting
+/// the autonomy ranges where the odds differ at both ends. All the searches share the precomputation of the heuristic.
+pub fn compute_autonomy_analysis(
+    hunter_planning: &BountyHunterPlanning,
+    galaxy_routes: &GalaxyRoutes,
+    planet_id_map: &PlanetCatalog,
+    mission: &Mission,
+) -> Result<Vec<AutonomyOdds>> {
+    let Some(search_context) =
+        SearchContext::new(hunter_planning, galaxy_routes, planet_id_map, mission)?
+    else {
+        return Ok(Vec::new());
+    };
+    let mut odds_cache = HashMap::new();
+    let mut odds = |autonomy: u64| -> Result<Option<f64>> {
+        if let Some(odds) = odds_cache.get(&autonomy) {
+            return Ok(*odds);
+        }
+        let odds = search_context
+            .search(autonomy, SearchMode::BestItineraries(1))?
+            .first()
+            .map(|mission_plan| mission_plan.probability_of_success);
+        odds_cache.insert(autonomy, odds);
+        Ok(odds)
+    };
+
+    autonomy_curve(mission.countdown, &mut odds)
+}
+
+/// Points of the autonomy curve from the odds of the best itinerary for each autonomy up to `max_autonomy`,
+/// or None when the mission is not feasible with this autonomy. `compute_odds` is expected to cache its results.
+///
+/// The bisection relies on the odds increasing with the autonomy. If the odds computed on the way contradict it,
+/// the curve found can't be trusted, and it is computed again by trying every autonomy up to the smallest one
+/// with the best odds computed, where the odds stop improving.
+fn autonomy_curve(
+    max_autonomy: u64,
+    compute_odds: &mut impl FnMut(u64) -> Result<Option<f64>>,
+) -> Result<Vec<AutonomyOdds>> {
+    let mut computed_odds = BTreeMap::new();
+    let mut odds = |autonomy: u64| -> Result<Option<f64>> {
+        let autonomy_odds = compute_odds(autonomy)?;
+        computed_odds.insert(autonomy, autonomy_odds);
+        Ok(autonomy_odds)
+    };
+    let Some(max_odds) = odds(max_autonomy)? else {
+        return Ok(Vec::new());
+    };
+
+    // smallest feasible autonomy: the mission is feasible with `feasible`, and not below `low`
+    let (mut low, mut feasible) = (0, max_autonomy);
+    while low < feasible {
+        let middle = low + (feasible - low) / 2;
+        match odds(middle)? {
+            Some(_) => feasible = middle,
+            None => low = middle + 1,
+        }
+    }
+
+    let mut curve = vec![AutonomyOdds {
+        autonomy: feasible,
+        probability_of_success: odds(feasible)?.unwrap_or_default(),
+    }];
+    // ranges (low, high) of autonomies where the odds at low are smaller than at high, so the odds improve
+    // at least once in ]low, high]. The ranges are processed from the lowest to keep the curve sorted
+    let mut ranges = vec![(feasible, max_autonomy)];
+    while let Some((low, high)) = ranges.pop() {
+        let low_odds = odds(low)?.unwrap_or_default();
+        let high_odds = odds(high)?.unwrap_or_default();
+        if low_odds >= high_odds {
+            continue;
+        }
+        if high == low + 1 {
+            curve.push(AutonomyOdds {
+                autonomy: high,
+                probability_of_success: high_odds,
+            });
+            continue;
+        }
+        let middle = low + (high - low) / 2;
+        ranges.push((middle, high));
+        ranges.push((low, middle));
+    }
+
+    // None < Some(_), so the feasibility and then the odds must never decrease with the autonomy
+    let increasing = computed_odds
+        .values()
+        .tuple_windows()
+        .all(|(odds, next_odds)| odds <= next_odds);
+    if increasing && curve.last().map(|point| point.probability_of_success) == Some(max_odds) {
+        return Ok(curve);
+    }
+    let best_odds =
+        computed_odds.values().copied().fold(
+            None,
+            |best, odds| {
+                if odds > best {
+                    odds
+                } else {
+                    best
+                }
+            },
+        );
+    let last_autonomy = computed_odds
+        .iter()
+        .find(|(_, odds)| **odds == best_odds)
+        .map_or(max_autonomy, |(autonomy, _)| *autonomy);
+    let mut curve: Vec<AutonomyOdds> = Vec::new();
+    for autonomy in 0..=last_autonomy {
+        let Some(probability_of_success) = compute_odds(autonomy)? else {
+            continue;
+        };
+        if curve
+            .last()
+            .is_some_and(|point| point.probability_of_success >= probability_of_success)
+        {
+            continue;
+        }
+        curve.push(AutonomyOdds {
+            autonomy,
+            probability_of_success,
+        });
+    }
+    Ok(curve)
+}
+
+/// Compute how much the odds of the mission improve when each sighting reported day by day is removed from the planning,
+/// to know which reports are worth verifying. The sightings are ranked by decreasing impact, then by planet and day.
+/// Reports of the same bounty hunters on the same planet and day are merged in a single sighting, and patrols are not part
+/// of the report.
+///
+/// Removing a sighting may open a better itinerary avoiding the other bounty hunters, so the mission is searched again
+/// for each sighting.
+pub fn compute_sensitivity_report(
+    hunter_planning: &BountyHunterPlanning,
+    galaxy_routes: &GalaxyRoutes,
+    planet_id_map: &PlanetCatalog,
+    mission: &Mission,
+) -> Result<SensitivityReport> {
+    let probability_of_success =
+        compute_probability_of_success(hunter_planning, galaxy_routes, planet_id_map, mission)?
+            .probability_of_success;
+
+    // the sightings are removed one at a time from the same copy, and put back after the search
+    let mut planning = hunter_planning.clone();
+    let mut sightings = Vec::new();
+    for (planet, day) in hunter_planning.sighting_days() {
+        let Some(sighting) = planning.remove_sighting(&planet, day) else {
+            continue;
+        };
+        let odds_without_sighting =
+            compute_probability_of_success(&planning, galaxy_routes, planet_id_map, mission)?
+                .probability_of_success;
+        planning.insert_sighting(planet, day, sighting);
+        sightings.push(SightingImpact {
+            planet,
+            day,
+            probability_of_success: odds_without_sighting,
+            // removing bounty hunters can't reduce the odds, only rounding errors can
+            impact: (odds_without_sighting - probability_of_success).max(0.),
+        });
+    }
+    // the sort is stable, so sightings with the same impact stay sorted by planet and day
+    sightings.sort_by(|a, b| b.impact.total_cmp(&a.impact));
+
+    Ok(SensitivityReport {
+        probability_of_success,
+        sightings,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        domain_models::{AutonomyOdds, CountdownOdds},
+        domain_services::test::{get_example_mission, get_example_universe},
+    };
+
+    use super::{
+        autonomy_curve, compute_autonomy_analysis, compute_minimum_countdown,
+        compute_sensitivity_report,
+    };
+
+    #[test]
+    fn test_compute_minimum_countdown() {
+        let (planet_id_map, galaxy_routes, hunter_planning) = get_example_universe();
+        let mission = get_example_mission(20);
+
+        let analysis = compute_minimum_countdown(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &mission,
+            0.9,
+        )
+        .unwrap();
+        assert_eq!(
+            analysis.curve,
+            [
+                CountdownOdds {
+                    countdown: 8,
+                    probability_of_success: 0.81
+                },
+                CountdownOdds {
+                    countdown: 9,
+                    probability_of_success: 0.9
+                },
+                CountdownOdds {
+                    countdown: 10,
+                    probability_of_success: 1.
+                }
+            ]
+        );
+        assert_eq!(analysis.minimum_countdown, Some(9));
+
+        // the odds can't reach the target before the limit
+        let analysis = compute_minimum_countdown(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &get_example_mission(9),
+            0.95,
+        )
+        .unwrap();
+        assert_eq!(analysis.curve.len(), 2);
+        assert_eq!(analysis.minimum_countdown, None);
+    }
+
+    #[test]
+    fn test_compute_autonomy_analysis() {
+        let (planet_id_map, galaxy_routes, hunter_planning) = get_example_universe();
+
+        // with a countdown of 8, the Millennium Falcon needs 6 days of autonomy to reach Endor through Hoth,
+        // and only meets the bounty hunters once when it doesn't need to refuel on Hoth
+        let analysis = compute_autonomy_analysis(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &get_example_mission(8),
+        )
+        .unwrap();
+        assert_eq!(
+            analysis,
+            [
+                AutonomyOdds {
+                    autonomy: 6,
+                    probability_of_success: 0.81
+                },
+                AutonomyOdds {
+                    autonomy: 7,
+                    probability_of_success: 0.9
+                }
+            ]
+        );
+
+        // with a countdown of 10, waiting on Tatooine avoids the bounty hunters, so more autonomy doesn't help
+        let analysis = compute_autonomy_analysis(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &get_example_mission(10),
+        )
+        .unwrap();
+        assert_eq!(
+            analysis,
+            [AutonomyOdds {
+                autonomy: 6,
+                probability_of_success: 1.
+            }]
+        );
+
+        // not feasible whatever the autonomy
+        let analysis = compute_autonomy_analysis(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &get_example_mission(6),
+        )
+        .unwrap();
+        assert!(analysis.is_empty());
+    }
+
+    #[test]
+    fn test_autonomy_curve_not_increasing() {
+        // the odds drop after the autonomy 4, which the bisection notices when computing them at 4 and 8
+        let mut odds = |autonomy: u64| {
+            Ok(match autonomy {
+                0..=2 => None,
+                3 => Some(0.5),
+                4 => Some(0.95),
+                _ => Some(0.9),
+            })
+        };
+        assert_eq!(
+            autonomy_curve(8, &mut odds).unwrap(),
+            [
+                AutonomyOdds {
+                    autonomy: 3,
+                    probability_of_success: 0.5
+                },
+                AutonomyOdds {
+                    autonomy: 4,
+                    probability_of_success: 0.95
+                }
+            ]
+        );
+
+        // the autonomies are tried one by one only up to the best odds, not up to a large countdown
+        let mut n_odds = 0;
+        let mut odds = |autonomy: u64| {
+            n_odds += 1;
+            Ok(match autonomy {
+                0..=2 => None,
+                3 => Some(0.5),
+                4 => Some(0.95),
+                _ => Some(0.9),
+            })
+        };
+        assert_eq!(autonomy_curve(1_000_000_000, &mut odds).unwrap().len(), 2);
+        assert!(n_odds < 200);
+    }
+
+    #[test]
+    fn test_compute_sensitivity_report() {
+        let (planet_id_map, galaxy_routes, hunter_planning) = get_example_universe();
+        let hoth = *planet_id_map.get("Hoth").unwrap();
+
+        // with a countdown of 8, the Millennium Falcon is on Hoth on days 6 and 7
+        let report = compute_sensitivity_report(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &get_example_mission(8),
+        )
+        .unwrap();
+        assert_eq!(report.probability_of_success, 0.81);
+        let impacts = report
+            .sightings
+            .iter()
+            .map(|sighting| {
+                (
+                    sighting.planet,
+                    sighting.day,
+                    sighting.probability_of_success,
+                )
+            })
+            .collect::<Vec<_>>();
+        // the bounty hunters of day 8 are never met
+        assert_eq!(impacts, [(hoth, 6, 0.9), (hoth, 7, 0.9), (hoth, 8, 0.81)]);
+        assert!((report.sightings[0].impact - 0.09).abs() < 1e-9);
+        assert_eq!(report.sightings[2].impact, 0.);
+    }
+}
@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+
+use crate::domain_models::{
+    BountyHunterPlanning, FleetPlan, GalaxyRoutes, Mission, PlanetCatalog, PlanetId,
+};
+
+use super::{compute_probability_of_success, evaluate_itinerary};
+
+/// Maximal number of uncertain sightings shared by several ships, as all their presences are enumerated
+const MAX_SHARED_SIGHTINGS: usize = 20;
+
+/// Compute the best itinerary of each ship of a fleet, all of them travelling through the same galaxy
+/// and facing the same bounty hunters, but with their own mission (autonomy, departure, ...).
+/// Each ship maximizes its own odds.
+///
+/// Once the presence of the bounty hunters is known, the captures of different ships are independent.
+/// But ships meeting the same uncertain sighting all find the bounty hunters there, or none of them does,
+/// so the probabilities that at least one ship arrives, and that all of them arrive, are computed for every
+/// presence of the shared uncertain sightings. The pursuers sent after a ship only chase this ship.
+pub fn compute_fleet_plan(
+    hunter_planning: &BountyHunterPlanning,
+    galaxy_routes: &GalaxyRoutes,
+    planet_id_map: &PlanetCatalog,
+    missions: &[Mission],
+) -> Result<FleetPlan> {
+    let mission_plans = missions
+        .iter()
+        .map(|mission| {
+            compute_probability_of_success(hunter_planning, galaxy_routes, planet_id_map, mission)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // a ship is on a planet at most once a day
+    let mut n_ships = HashMap::new();
+    for itinerary in mission_plans
+        .iter()
+        .filter_map(|mission_plan| mission_plan.itinerary.as_ref())
+    {
+        for step in itinerary.steps() {
+            if hunter_planning
+                .sighting(&step.planet, step.day)
+                .is_some_and(|sighting| sighting.presence_probability < 1.)
+            {
+                *n_ships.entry((step.planet, step.day)).or_insert(0) += 1;
+            }
+        }
+    }
+    let mut shared_sightings: Vec<(PlanetId, u64)> = n_ships
+        .into_iter()
+        .filter(|(_, n)| *n > 1)
+        .map(|(planet_day, _)| planet_day)
+        .collect();
+    shared_sightings.sort_unstable();
+    if shared_sightings.len() > MAX_SHARED_SIGHTINGS {
+        return Err(anyhow!(
+            "the ships share more than {MAX_SHARED_SIGHTINGS} uncertain sightings"
+        ));
+    }
+
+    let mut probability_all = 0.;
+    let mut probability_none = 0.;
+    // bit `i` of `presences` is set when the bounty hunters of the `i`-th shared sighting are there
+    for presences in 0..1_u32 << shared_sightings.len() {
+        let mut probability = 1.;
+        let mut presence = HashMap::new();
+        for (i, (planet, day)) in shared_sightings.iter().enumerate() {
+            let present = presences & (1 << i) != 0;
+            let presence_probability = hunter_planning
+                .sighting(planet, *day)
+                .map_or(1., |sighting| sighting.presence_probability);
+            probability *= if present {
+                presence_probability
+            } else {
+                1. - presence_probability
+            };
+            presence.insert((*planet, *day), present);
+        }
+        let survivals = mission_plans
+            .iter()
+            .map(|mission_plan| {
+                mission_plan.itinerary.as_ref().map_or(0., |itinerary| {
+                    evaluate_itinerary(hunter_planning, galaxy_routes, itinerary, &presence).0
+                })
+            })
+            .collect::<Vec<_>>();
+        probability_all += probability * survivals.iter().product::<f64>();
+        probability_none += probability
+            * survivals
+                .iter()
+                .map(|survival| 1. - survival)
+                .product::<f64>();
+    }
+    Ok(FleetPlan {
+        mission_plans,
+        probability_at_least_one: 1. - probability_none,
+        probability_all,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        domain_models::{BountyHunterPlanning, Departure, GeometricCaptureModel, Mission},
+        domain_services::test::{get_example_mission, get_example_universe},
+    };
+
+    use super::compute_fleet_plan;
+
+    #[test]
+    fn test_compute_fleet_plan() {
+        let (planet_id_map, galaxy_routes, hunter_planning) = get_example_universe();
+        let millennium_falcon = get_example_mission(8);
+        // an escort starting from Dagobah reaches Endor before the bounty hunters arrive on Hoth
+        let dagobah_escort = Mission {
+            autonomy: 3,
+            departures: vec![Departure {
+                planet: "Dagobah".to_string(),
+                start_day: 0,
+            }],
+            ..millennium_falcon.clone()
+        };
+        // an escort with a too small autonomy can't leave Tatooine
+        let small_escort = Mission {
+            autonomy: 5,
+            ..millennium_falcon.clone()
+        };
+        let compute = |missions: &[Mission]| {
+            let fleet_plan =
+                compute_fleet_plan(&hunter_planning, &galaxy_routes, &planet_id_map, missions)
+                    .unwrap();
+            (
+                fleet_plan
+                    .mission_plans
+                    .iter()
+                    .map(|mission_plan| mission_plan.probability_of_success)
+                    .collect::<Vec<_>>(),
+                fleet_plan.probability_at_least_one,
+                fleet_plan.probability_all,
+            )
+        };
+
+        let (odds, at_least_one, all) = compute(&[millennium_falcon.clone(), dagobah_escort]);
+        assert_eq!(odds, [0.81, 1.]);
+        assert_eq!(at_least_one, 1.);
+        assert_eq!(all, 0.81);
+
+        let (odds, at_least_one, all) = compute(&[millennium_falcon.clone(), small_escort]);
+        assert_eq!(odds, [0.81, 0.]);
+        assert_eq!(at_least_one, 0.81);
+        assert_eq!(all, 0.);
+
+        let (_, at_least_one, all) = compute(&[millennium_falcon.clone(), millennium_falcon]);
+        assert!((at_least_one - (1. - 0.19 * 0.19)).abs() < 1e-10);
+        assert!((all - 0.81 * 0.81).abs() < 1e-10);
+    }
+
+    #[test]
+    fn test_compute_fleet_plan_shared_sighting() {
+        let (planet_id_map, galaxy_routes, _) = get_example_universe();
+        let hoth_id = *planet_id_map.get("Hoth").unwrap();
+        // the bounty hunters are on Hoth on day 7 half of the time, where two ships refuel before reaching Endor
+        let hunter_planning = BountyHunterPlanning::with_presence_probabilities(
+            [(hoth_id, [(7, 0.5)].into_iter().collect())]
+                .into_iter()
+                .collect(),
+            &GeometricCaptureModel {
+                capture_probability: 0.2,
+            },
+        );
+        let mission = get_example_mission(8);
+        let fleet_plan = compute_fleet_plan(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &[mission.clone(), mission],
+        )
+        .unwrap();
+        for mission_plan in fleet_plan.mission_plans.iter() {
+            assert!((mission_plan.probability_of_success - 0.9).abs() < 1e-10);
+        }
+        // both ships meet the bounty hunters, or none of them does
+        assert!((fleet_plan.probability_all - (0.5 * 0.8 * 0.8 + 0.5)).abs() < 1e-10);
+        assert!((fleet_plan.probability_at_least_one - (1. - 0.5 * 0.2 * 0.2)).abs() < 1e-10);
+    }
+}
@@ -0,0 +1,2028 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+};
+
+use anyhow::{anyhow, Result};
+
+pub mod adversary;
+pub mod analysis;
+pub mod fleet;
+pub mod robust;
+pub mod simulator;
+pub mod stochastic;
+
+use crate::domain_models::{
+    Action, BountyHunterPlanning, EncounterOutcome, GalaxyRoutes, Itinerary, Mission, MissionPlan,
+    PlanetCatalog, PlanetId, Step,
+};
+
+/// Risk for the Millennium Falcon to have been captured, as the sum of -ln(1 - capture probability)
+/// of all the bounty hunters met. The probability of success is then exp(-risk).
+/// Summing logarithms instead of multiplying probabilities makes the risk an additive cost, like a distance
+/// in a classic shortest path problem, and doesn't lose precision after many encounters.
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Risk(f64);
+
+impl Risk {
+    fn meet(self, capture_probability: f64) -> Risk {
+        Risk(self.0 - (-capture_probability).ln_1p())
+    }
+
+    /// Risk of following a branch taken with this probability
+    fn branch(self, probability: f64) -> Risk {
+        if probability == 1. {
+            return self;
+        }
+        Risk(self.0 - probability.ln())
+    }
+
+    /// Risk of being in one branch or the other, as -ln(exp(-risk) + exp(-other risk))
+    fn merge(self, other: Risk) -> Risk {
+        let (low, high) = (self.min(other).0, self.max(other).0);
+        if high == f64::INFINITY {
+            return Risk(low);
+        }
+        Risk(low - (low - high).exp().ln_1p())
+    }
+}
+
+// a risk is never NaN, so it can be totally ordered
+impl Eq for Risk {}
+
+impl PartialOrd for Risk {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Risk {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// What the future of the Millennium Falcon depends on, besides its position. Bounty hunters that may not be
+/// there make it uncertain, so a state has several hypotheses, depending on which bounty hunters really spotted it
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Default, PartialOrd, Ord)]
+struct Hypothesis {
+    /// planets and days, after the current one, where pursuers will arrive. Sorted
+    pursuers: Vec<(PlanetId, u64)>,
+    /// true if the bounty hunters on the planet were already met during this stay, and count once
+    met_hunters: bool,
+}
+
+impl Hypothesis {
+    /// Same hypothesis with the days of the pursuers counted from `day`. Once no bounty hunter can be met
+    /// anymore, the future only depends on when the pursuers arrive, not on the day
+    fn relative_to(&self, day: u64) -> Hypothesis {
+        Hypothesis {
+            pursuers: self
+                .pursuers
+                .iter()
+                .map(|(planet, pursuer_day)| (*planet, pursuer_day - day))
+                .collect(),
+            met_hunters: self.met_hunters,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct State {
+    /// risk over all the hypotheses
+    risk: Risk,
+    elapsed_time: u64,
+    time_to_destination: u64,
+    fuel: u64,
+    /// number of days already spent refuelling, for refuels taking several days
+    refuelling: u64,
+    planet: PlanetId,
+    /// bit `i` is set when the `i`-th waypoint of the mission has been visited
+    waypoints: u64,
+    /// hypotheses with the risk of being in each one without having been captured. Sorted
+    hypotheses: Vec<(Hypothesis, Risk)>,
+}
+
+/// Part of a state that defines what the Millennium Falcon can do next.
+/// Two states at the same position have the same future in each hypothesis, so a state dominates another one
+/// when its risk is lower in every hypothesis.
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct Position {
+    elapsed_time: u64,
+    fuel: u64,
+    refuelling: u64,
+    planet: PlanetId,
+    waypoints: u64,
+    hypotheses: Vec<Hypothesis>,
+}
+
+/// Position without the day. After the last event, a state is dominated by the states safer in every hypothesis
+/// that were at the same situation on the same day or sooner, as they can do the same journey sooner.
+/// The pursuers still coming are part of the situation, counted from the day of the state,
+/// so that they arrive at the same time in this earlier journey. The patrols without end are still there,
+/// so the day in their cycle is part of the situation too, and the earlier journey meets the same patrols.
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct Situation {
+    /// day in the cycle of the patrols without end
+    phase: u64,
+    fuel: u64,
+    refuelling: u64,
+    planet: PlanetId,
+    waypoints: u64,
+    hypotheses: Vec<Hypothesis>,
+}
+
+impl State {
+    fn position(&self) -> Position {
+        Position {
+            elapsed_time: self.elapsed_time,
+            fuel: self.fuel,
+            refuelling: self.refuelling,
+            planet: self.planet,
+            waypoints: self.waypoints,
+            hypotheses: self
+                .hypotheses
+                .iter()
+                .map(|(hypothesis, _)| hypothesis.clone())
+                .collect(),
+        }
+    }
+
+    /// Risk in each hypothesis of the position
+    fn risks(&self) -> Vec<Risk> {
+        self.hypotheses.iter().map(|(_, risk)| *risk).collect()
+    }
+
+    fn situation(&self, patrol_cycle: u64) -> Situation {
+        Situation {
+            phase: self.elapsed_time % patrol_cycle,
+            fuel: self.fuel,
+            refuelling: self.refuelling,
+            planet: self.planet,
+            waypoints: self.waypoints,
+            hypotheses: self
+                .hypotheses
+                .iter()
+                .map(|(hypothesis, _)| hypothesis.relative_to(self.elapsed_time))
+                .collect(),
+        }
+    }
+}
+
+impl PartialOrd for State {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for State {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        // the safest state should be explored first
+        if self.risk != other.risk {
+            return self.risk.cmp(&other.risk);
+        }
+        let sum_time = self.elapsed_time + self.time_to_destination;
+        let other_sum_time = other.elapsed_time + other.time_to_destination;
+        sum_time.cmp(&other_sum_time)
+    }
+}
+
+#[derive(PartialEq, Eq)]
+struct AllTimeState {
+    time: u64,
+    planet_id: PlanetId,
+}
+
+impl PartialOrd for AllTimeState {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for AllTimeState {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.time.cmp(&other.time)
+    }
+}
+
+/// run a Dijkstra algorithm to compute the minimal distance from a planet to the closest destination,
+/// without considering the bounty hunters.
+/// As routes are directed, the Dijkstra runs from all the destinations at once on the reversed galaxy.
+/// the output of this function will be the A* heuristic
+fn compute_all_time_to_destination(
+    galaxy_routes: &GalaxyRoutes,
+    destination_ids: &HashSet<PlanetId>,
+) -> Result<HashMap<PlanetId, u64>> {
+    let galaxy_routes = galaxy_routes.reversed();
+    let mut time_to_destination = HashMap::new();
+    let mut planet_to_process = destination_ids
+        .iter()
+        .map(|destination_id| {
+            Reverse(AllTimeState {
+                time: 0,
+                planet_id: *destination_id,
+            })
+        })
+        .collect::<BinaryHeap<_>>();
+
+    while let Some(Reverse(state)) = planet_to_process.pop() {
+        if let std::collections::hash_map::Entry::Vacant(e) =
+            time_to_destination.entry(state.planet_id)
+        {
+            // first time we see this planet
+            e.insert(state.time);
+        } else {
+            // this planet has already been processed
+            continue;
+        }
+        for (neighbour_planet_id, time) in galaxy_routes.get(&state.planet_id)? {
+            planet_to_process.push(Reverse(AllTimeState {
+                time: state.time + time,
+                planet_id: *neighbour_planet_id,
+            }));
+        }
+    }
+    Ok(time_to_destination)
+}
+
+/// Waypoints of a mission, identified by their index in a bitset
+struct WaypointTracker {
+    waypoints: Vec<(PlanetId, Option<u64>)>,
+    ordered: bool,
+}
+
+impl WaypointTracker {
+    /// Return None if a waypoint is not connected to the other planets, so can't be visited
+    fn new(mission: &Mission, planet_id_map: &PlanetCatalog) -> Result<Option<WaypointTracker>> {
+        if mission.waypoints.len() > u64::BITS as usize {
+            return Err(anyhow!(
+                "a mission can't have more than {} waypoints",
+                u64::BITS
+            ));
+        }
+        let waypoints = mission
+            .waypoints
+            .iter()
+            .map(|waypoint| {
+                planet_id_map
+                    .get(&waypoint.planet)
+                    .map(|planet_id| (*planet_id, waypoint.deadline))
+            })
+            .collect::<Option<Vec<_>>>();
+        Ok(waypoints.map(|waypoints| WaypointTracker {
+            waypoints,
+            ordered: mission.ordered_waypoints,
+        }))
+    }
+
+    /// Waypoints visited after landing on `planet` at `day`. A waypoint reached after its deadline doesn't count
+    fn visit(&self, mut visited: u64, planet: &PlanetId, day: u64) -> u64 {
+        let is_visited_now = |(waypoint, deadline): &(PlanetId, Option<u64>)| {
+            waypoint == planet && deadline.is_none_or(|deadline| day <= deadline)
+        };
+        if self.ordered {
+            // only the next waypoint can be visited, or the following ones if they are on the same planet
+            while let Some(waypoint) = self.waypoints.get(visited.count_ones() as usize) {
+                if !is_visited_now(waypoint) {
+                    break;
+                }
+                visited |= 1 << visited.count_ones();
+            }
+            return visited;
+        }
+        for (i, waypoint) in self.waypoints.iter().enumerate() {
+            if is_visited_now(waypoint) {
+                visited |= 1 << i;
+            }
+        }
+        visited
+    }
+
+    fn all_visited(&self, visited: u64) -> bool {
+        visited.count_ones() as usize == self.waypoints.len()
+    }
+
+    /// Return true if a waypoint not visited yet had to be visited before this day
+    fn missed_deadline(&self, visited: u64, day: u64) -> bool {
+        self.waypoints
+            .iter()
+            .enumerate()
+            .any(|(i, (_, deadline))| visited & (1 << i) == 0 && deadline.is_some_and(|d| d < day))
+    }
+}
+
+/// Node of the A* search tree: a state, how it was reached and the index of its parent in the explored nodes
+#[derive(Debug, PartialEq, Eq)]
+struct Node {
+    state: State,
+    action: Action,
+    bounty_hunter: bool,
+    parent: Option<usize>,
+}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.state.cmp(&other.state)
+    }
+}
+
+/// Follow the parents of an explored node back to the departure to rebuild the itinerary
+fn build_itinerary(explored: &[Node], last_node: usize) -> Itinerary {
+    let mut steps = Vec::new();
+    let mut node_index = Some(last_node);
+    while let Some(index) = node_index {
+        let node = &explored[index];
+        steps.push(Step {
+            planet: node.state.planet,
+            day: node.state.elapsed_time,
+            fuel: node.state.fuel,
+            action: node.action,
+            bounty_hunter: node.bounty_hunter,
+        });
+        node_index = node.parent;
+    }
+    steps.reverse();
+    Itinerary::new(steps)
+}
+
+pub fn compute_probability_of_success(
+    hunter_planning: &BountyHunterPlanning,
+    galaxy_routes: &GalaxyRoutes,
+    planet_id_map: &PlanetCatalog,
+    mission: &Mission,
+) -> Result<MissionPlan> {
+    let best_mission_plan =
+        compute_best_mission_plans(hunter_planning, galaxy_routes, planet_id_map, mission, 1)?
+            .into_iter()
+            .next();
+    Ok(best_mission_plan.unwrap_or(MissionPlan {
+        probability_of_success: 0.,
+        itinerary: None,
+    }))
+}
+
+/// Compute the `k` best distinct itineraries reaching the arrival planet before the end of the countdown,
+/// ranked by odds, then by arrival day. Less than `k` itineraries are returned if there are not enough of them.
+pub fn compute_best_mission_plans(
+    hunter_planning: &BountyHunterPlanning,
+    galaxy_routes: &GalaxyRoutes,
+    planet_id_map: &PlanetCatalog,
+    mission: &Mission,
+    k: usize,
+) -> Result<Vec<MissionPlan>> {
+    search(
+        hunter_planning,
+        galaxy_routes,
+        planet_id_map,
+        mission,
+        SearchMode::BestItineraries(k),
+    )
+}
+
+/// Compute the Pareto frontier of the arrival day versus the risk of being captured:
+/// for every arrival day before the end of the countdown, the best itinerary arriving on this day is kept
+/// only if no other itinerary arrives sooner with at least the same odds.
+/// The itineraries are sorted by decreasing odds, so by decreasing arrival day.
+pub fn compute_pareto_frontier(
+    hunter_planning: &BountyHunterPlanning,
+    galaxy_routes: &GalaxyRoutes,
+    planet_id_map: &PlanetCatalog,
+    mission: &Mission,
+) -> Result<Vec<MissionPlan>> {
+    search(
+        hunter_planning,
+        galaxy_routes,
+        planet_id_map,
+        mission,
+        SearchMode::ParetoFrontier,
+    )
+}
+
+/// What the A* search does when it reaches the arrival planet
+enum SearchMode {
+    /// Stop after `k` itineraries. To find distinct itineraries, a position can be explored up to `k` times:
+    /// the n-th time the arrival planet is reached, it is by the n-th best itinerary.
+    BestItineraries(usize),
+    /// Continue searching for itineraries arriving strictly sooner than the ones already found.
+    /// As the states are explored by increasing risk, each new itinerary is
+    /// on the Pareto frontier.
+    ParetoFrontier,
+}
+
+fn search(
+    hunter_planning: &BountyHunterPlanning,
+    galaxy_routes: &GalaxyRoutes,
+    planet_id_map: &PlanetCatalog,
+    mission: &Mission,
+    search_mode: SearchMode,
+) -> Result<Vec<MissionPlan>> {
+    match SearchContext::new(hunter_planning, galaxy_routes, planet_id_map, mission)? {
+        Some(search_context) => search_context.search(mission.autonomy, search_mode),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Everything the search needs that doesn't depend on the autonomy of the Millennium Falcon,
+/// so analyses running several searches only compute it once
+struct SearchContext<'a> {
+    hunter_planning: &'a BountyHunterPlanning,
+    galaxy_routes: &'a GalaxyRoutes,
+    planet_id_map: &'a PlanetCatalog,
+    mission: &'a Mission,
+    forbidden_planets: HashSet<PlanetId>,
+    /// departure planets with their start day
+    departures: Vec<(PlanetId, u64)>,
+    arrival_ids: HashSet<PlanetId>,
+    waypoint_tracker: WaypointTracker,
+    all_time_to_destination: HashMap<PlanetId, u64>,
+    /// after this day, the risk can't change anymore, except for the patrols without end
+    last_event_day: Option<u64>,
+    /// number of days after which the patrols without end are back
+    patrol_cycle: u64,
+}
+
+impl<'a> SearchContext<'a> {
+    /// Return None if the mission is not feasible whatever the autonomy
+    fn new(
+        hunter_planning: &'a BountyHunterPlanning,
+        galaxy_routes: &'a GalaxyRoutes,
+        planet_id_map: &'a PlanetCatalog,
+        mission: &'a Mission,
+    ) -> Result<Option<SearchContext<'a>>> {
+        let forbidden_planets = mission
+            .forbidden_planets
+            .iter()
+            .filter_map(|planet| planet_id_map.get(planet).copied())
+            .collect::<HashSet<_>>();
+
+        // departure planets not connected to the other planets are ignored. This is sad
+        let departures = mission
+            .departures
+            .iter()
+            .filter_map(|departure| {
+                planet_id_map
+                    .get(&departure.planet)
+                    .map(|planet_id| (*planet_id, departure.start_day))
+            })
+            .filter(|(planet_id, _)| !forbidden_planets.contains(planet_id))
+            .collect::<Vec<_>>();
+
+        // arrival planets not connected to other planets are ignored. How did the rebel get there ?
+        let arrival_ids = mission
+            .arrivals
+            .iter()
+            .filter_map(|arrival| planet_id_map.get(arrival).copied())
+            .filter(|planet_id| !forbidden_planets.contains(planet_id))
+            .collect::<HashSet<_>>();
+
+        if departures.is_empty() || arrival_ids.is_empty() {
+            return Ok(None);
+        }
+
+        let waypoint_tracker = match WaypointTracker::new(mission, planet_id_map)? {
+            Some(v) => v,
+            None => return Ok(None), // a waypoint is not connected to the other planets
+        };
+
+        let all_time_to_destination = compute_all_time_to_destination(galaxy_routes, &arrival_ids)?;
+
+        // after the last bounty hunter and the last route closure, the end of the journey only depends on the
+        // pursuers and on the cycle of the patrols without end, whatever the countdown
+        let mut last_event_day = hunter_planning.last_day().max(
+            hunter_planning
+                .last_closure_day()
+                .map(|day| day.saturating_add(1)),
+        );
+        let patrol_cycle = hunter_planning.patrol_cycle().unwrap_or_else(|| {
+            // the patrols are never all back, so the day always matters
+            last_event_day = Some(u64::MAX);
+            1
+        });
+
+        Ok(Some(SearchContext {
+            hunter_planning,
+            galaxy_routes,
+            planet_id_map,
+            mission,
+            forbidden_planets,
+            departures,
+            arrival_ids,
+            waypoint_tracker,
+            all_time_to_destination,
+            last_event_day,
+            patrol_cycle,
+        }))
+    }
+
+    /// create the node reached from `parent` by `action`, considering the bounty hunters met in each hypothesis
+    /// and the waypoints visited on arrival
+    #[allow(clippy::too_many_arguments)]
+    fn new_node(
+        &self,
+        parent: Option<usize>,
+        hypotheses: &[(Hypothesis, Risk)],
+        planet: PlanetId,
+        elapsed_time: u64,
+        fuel: u64,
+        refuelling: u64,
+        waypoints: u64,
+        action: Action,
+    ) -> Node {
+        let mut next_hypotheses: Vec<(Hypothesis, Risk)> = Vec::new();
+        let mut bounty_hunter = false;
+        for (hypothesis, risk) in hypotheses {
+            for (outcome, next_hypothesis) in hypothesis_outcomes(
+                self.hunter_planning,
+                self.galaxy_routes,
+                hypothesis,
+                &planet,
+                elapsed_time,
+                action,
+                None,
+            ) {
+                let mut risk = risk.branch(outcome.probability);
+                if let Some(capture_probability) = outcome.capture_probability {
+                    bounty_hunter = true;
+                    risk = risk.meet(capture_probability);
+                }
+                match next_hypotheses
+                    .iter_mut()
+                    .find(|(hypothesis, _)| *hypothesis == next_hypothesis)
+                {
+                    Some((_, merged_risk)) => *merged_risk = merged_risk.merge(risk),
+                    None => next_hypotheses.push((next_hypothesis, risk)),
+                }
+            }
+        }
+        next_hypotheses.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+        Node {
+            state: State {
+                risk: next_hypotheses
+                    .iter()
+                    .map(|(_, risk)| *risk)
+                    .reduce(Risk::merge)
+                    .unwrap_or(Risk(f64::INFINITY)),
+                elapsed_time,
+                time_to_destination: *self
+                    .all_time_to_destination
+                    .get(&planet)
+                    .unwrap_or(&u64::MAX),
+                fuel,
+                refuelling,
+                planet,
+                waypoints: self
+                    .waypoint_tracker
+                    .visit(waypoints, &planet, elapsed_time),
+                hypotheses: next_hypotheses,
+            },
+            action,
+            bounty_hunter,
+            parent,
+        }
+    }
+
+    /// Run the A* search for a Millennium Falcon with the given autonomy.
+    /// Until the last event, the bounty hunters, the first day of the patrols without end and the route closures,
+    /// the days are explored one by one. After it, states are compared by situation, without the day, so the
+    /// number of explored states doesn't depend on the countdown. This is not a closed form of the end of the
+    /// journey: a last event on a far day, or patrols without end whose cycle doesn't fit in a u64,
+    /// make the search explore every day until the end of the countdown
+    fn search(&self, autonomy: u64, search_mode: SearchMode) -> Result<Vec<MissionPlan>> {
+        let mut mission_plans = Vec::new();
+        let max_explorations = match search_mode {
+            SearchMode::BestItineraries(0) => return Ok(mission_plans),
+            SearchMode::BestItineraries(k) => k,
+            SearchMode::ParetoFrontier => 1,
+        };
+        // last day to reach the arrival planet, reduced each time a new itinerary is found in ParetoFrontier mode
+        let mut deadline = self.mission.countdown;
+
+        let mut state_to_process = self
+            .departures
+            .iter()
+            .map(|(departure_id, start_day)| {
+                Reverse(self.new_node(
+                    None,
+                    &[(Hypothesis::default(), Risk(0.))],
+                    *departure_id,
+                    *start_day,
+                    autonomy,
+                    0,
+                    0,
+                    Action::Departure,
+                ))
+            })
+            .collect::<BinaryHeap<_>>();
+
+        // risks of the states explored at each position. As states are explored by increasing risk, a state is
+        // skipped when enough safer states in every hypothesis were explored at its position
+        let mut seen_state: HashMap<Position, Vec<Vec<Risk>>> = HashMap::new();
+        // after the last bounty hunter, the days and risks of the explorations of each situation
+        let mut seen_situation: HashMap<Situation, Vec<(u64, Vec<Risk>)>> = HashMap::new();
+        let mut explored = Vec::new();
+
+        while let Some(Reverse(node)) = state_to_process.pop() {
+            if self
+                .last_event_day
+                .is_none_or(|day| node.state.elapsed_time >= day)
+            {
+                // the day is not part of the position anymore, so the number of explorations doesn't depend on the countdown
+                let risks = node.state.risks();
+                let seen = seen_situation
+                    .entry(node.state.situation(self.patrol_cycle))
+                    .or_default();
+                let n_seen = seen
+                    .iter()
+                    .filter(|(day, seen_risks)| {
+                        *day <= node.state.elapsed_time && is_safer(seen_risks, &risks)
+                    })
+                    .count();
+                if n_seen >= max_explorations {
+                    // safer states were already there sooner
+                    continue;
+                }
+                seen.push((node.state.elapsed_time, risks));
+            } else {
+                let risks = node.state.risks();
+                let seen_risks = seen_state.entry(node.state.position()).or_default();
+                let n_seen = seen_risks
+                    .iter()
+                    .filter(|seen_risks| is_safer(seen_risks, &risks))
+                    .count();
+                if n_seen >= max_explorations {
+                    // this position has already been explored enough times, by safer states
+                    continue;
+                }
+                seen_risks.push(risks);
+            }
+            if node
+                .state
+                .elapsed_time
+                .saturating_add(node.state.time_to_destination)
+                > deadline
+            {
+                // then it is not possible to reach the destination from this state
+                continue;
+            }
+            if self
+                .waypoint_tracker
+                .missed_deadline(node.state.waypoints, node.state.elapsed_time)
+            {
+                continue;
+            }
+
+            explored.push(node);
+            let node_index = explored.len() - 1;
+            let state = &explored[node_index].state;
+
+            if self.arrival_ids.contains(&state.planet)
+                && self.waypoint_tracker.all_visited(state.waypoints)
+            {
+                let itinerary = build_itinerary(&explored, node_index);
+                mission_plans.push(MissionPlan {
+                    probability_of_success: compute_itinerary_probability_of_success(
+                        self.hunter_planning,
+                        self.galaxy_routes,
+                        &itinerary,
+                    ),
+                    itinerary: Some(itinerary),
+                });
+                match search_mode {
+                    SearchMode::BestItineraries(k) if mission_plans.len() == k => break,
+                    SearchMode::BestItineraries(_) => {}
+                    // only itineraries arriving sooner are now interesting
+                    SearchMode::ParetoFrontier => match state.elapsed_time.checked_sub(1) {
+                        Some(day) => deadline = day,
+                        None => break,
+                    },
+                }
+                continue;
+            }
+
+            // Millennium Falcon can refuel if there is a fuel depot, else only wait.
+            // Waiting is not considered when refuelling is possible, as refuelling gives the same options with more fuel
+            let (fuel, refuelling, action) =
+                if self.planet_id_map.has_fuel_depot(&state.planet) && state.fuel < autonomy {
+                    let (fuel, refuelling) =
+                        self.mission
+                            .refuel_model
+                            .refuel(state.fuel, state.refuelling, autonomy);
+                    (fuel, refuelling, Action::Refuel)
+                } else {
+                    (state.fuel, 0, Action::Wait)
+                };
+            state_to_process.push(Reverse(self.new_node(
+                Some(node_index),
+                &state.hypotheses,
+                state.planet,
+                state.elapsed_time + 1,
+                fuel,
+                refuelling,
+                state.waypoints,
+                action,
+            )));
+
+            // or visit neightbours planets, if it has enough fluel and the route is not closed today.
+            // Closures only remove departures, so the heuristic ignoring them stays admissible
+            for (new_planet_id, time) in self.galaxy_routes.get(&state.planet)? {
+                if *time > state.fuel
+                    || self.forbidden_planets.contains(new_planet_id)
+                    || self.hunter_planning.is_route_closed(
+                        &state.planet,
+                        new_planet_id,
+                        state.elapsed_time,
+                    )
+                {
+                    continue;
+                }
+                state_to_process.push(Reverse(self.new_node(
+                    Some(node_index),
+                    &state.hypotheses,
+                    *new_planet_id,
+                    state.elapsed_time + time,
+                    state.fuel - time,
+                    0,
+                    state.waypoints,
+                    Action::Travel,
+                )));
+            }
+        }
+
+        Ok(mission_plans)
+    }
+}
+
+/// Pursuers still to come after the Millennium Falcon is on the planet this day, with the new ones if it is `spotted`
+fn next_pursuers(
+    hunter_planning: &BountyHunterPlanning,
+    galaxy_routes: &GalaxyRoutes,
+    pursuers: &[(PlanetId, u64)],
+    planet: &PlanetId,
+    day: u64,
+    spotted: bool,
+) -> Vec<(PlanetId, u64)> {
+    let new_pursuers = hunter_planning
+        .pursuit()
+        .filter(|_| spotted)
+        .into_iter()
+        .flat_map(|pursuit| pursuit.pursuers(galaxy_routes, planet, day));
+    let mut pursuers = pursuers
+        .iter()
+        .copied()
+        .chain(new_pursuers)
+        // the Millennium Falcon can't meet the pursuers of the past anymore
+        .filter(|(_, pursuer_day)| *pursuer_day > day)
+        .collect::<Vec<_>>();
+    pursuers.sort_unstable();
+    pursuers.dedup();
+    pursuers
+}
+
+/// Return true if the risks are at most the other ones in every hypothesis
+fn is_safer(risks: &[Risk], other_risks: &[Risk]) -> bool {
+    risks
+        .iter()
+        .zip(other_risks)
+        .all(|(risk, other_risk)| risk <= other_risk)
+}
+
+/// Outcomes of the Millennium Falcon being on the planet this day after `action` in a hypothesis,
+/// with the hypothesis each one leads to. `presence` is the presence of the bounty hunters of the planning,
+/// if it is known
+fn hypothesis_outcomes(
+    hunter_planning: &BountyHunterPlanning,
+    galaxy_routes: &GalaxyRoutes,
+    hypothesis: &Hypothesis,
+    planet: &PlanetId,
+    day: u64,
+    action: Action,
+    presence: Option<bool>,
+) -> Vec<(EncounterOutcome, Hypothesis)> {
+    let outcomes = match hunter_planning.encounter(
+        planet,
+        day,
+        action,
+        &hypothesis.pursuers,
+        hypothesis.met_hunters,
+    ) {
+        Some(encounter) => encounter.outcomes(presence),
+        None => vec![EncounterOutcome {
+            probability: 1.,
+            capture_probability: None,
+            met_hunters: false,
+        }],
+    };
+    outcomes
+        .into_iter()
+        .map(|outcome| {
+            let next_hypothesis = Hypothesis {
+                pursuers: next_pursuers(
+                    hunter_planning,
+                    galaxy_routes,
+                    &hypothesis.pursuers,
+                    planet,
+                    day,
+                    outcome.capture_probability.is_some(),
+                ),
+                met_hunters: outcome.met_hunters,
+            };
+            (outcome, next_hypothesis)
+        })
+        .collect()
+}
+
+/// Probability of success of the Millennium Falcon following an itinerary, and for each step whether it may meet
+/// bounty hunters there. `presences` gives the presence of the bounty hunters of the planning on some planets
+/// and days, when it is known
+fn evaluate_itinerary(
+    hunter_planning: &BountyHunterPlanning,
+    galaxy_routes: &GalaxyRoutes,
+    itinerary: &Itinerary,
+    presences: &HashMap<(PlanetId, u64), bool>,
+) -> (f64, Vec<bool>) {
+    // hypotheses with their probability, and the capture probabilities of the bounty hunters met in them
+    let mut hypotheses = vec![(Hypothesis::default(), 1., Vec::new())];
+    let mut bounty_hunters = Vec::new();
+    for step in itinerary.steps() {
+        let mut next_hypotheses: Vec<(Hypothesis, f64, Vec<f64>)> = Vec::new();
+        let mut bounty_hunter = false;
+        for (hypothesis, probability, capture_probabilities) in hypotheses.iter() {
+            for (outcome, next_hypothesis) in hypothesis_outcomes(
+                hunter_planning,
+                galaxy_routes,
+                hypothesis,
+                &step.planet,
+                step.day,
+                step.action,
+                presences.get(&(step.planet, step.day)).copied(),
+            ) {
+                let probability = probability * outcome.probability;
+                let mut capture_probabilities = capture_probabilities.clone();
+                if let Some(capture_probability) = outcome.capture_probability {
+                    bounty_hunter = true;
+                    capture_probabilities.push(capture_probability);
+                }
+                match next_hypotheses
+                    .iter_mut()
+                    .find(|(hypothesis, _, _)| *hypothesis == next_hypothesis)
+                {
+                    // both hypotheses continue as one, with the capture probability giving the same survival
+                    Some((_, merged_probability, merged_capture_probabilities)) => {
+                        let survival = *merged_probability
+                            * (1. - probability_been_captured(merged_capture_probabilities))
+                            + probability
+                                * (1. - probability_been_captured(&capture_probabilities));
+                        *merged_probability += probability;
+                        if *merged_probability > 0. {
+                            *merged_capture_probabilities =
+                                vec![1. - survival / *merged_probability];
+                        }
+                    }
+                    None => {
+                        next_hypotheses.push((next_hypothesis, probability, capture_probabilities))
+                    }
+                }
+            }
+        }
+        hypotheses = next_hypotheses;
+        bounty_hunters.push(bounty_hunter);
+    }
+    let probability_of_success = hypotheses
+        .iter()
+        .map(|(_, probability, capture_probabilities)| {
+            probability * (1. - probability_been_captured(capture_probabilities))
+        })
+        .sum();
+    (probability_of_success, bounty_hunters)
+}
+
+/// Probability of success of the Millennium Falcon following an itinerary,
+/// considering all the bounty hunters of the planning met on the way, and the pursuers sent after it
+/// by the bounty hunters really there
+pub fn compute_itinerary_probability_of_success(
+    hunter_planning: &BountyHunterPlanning,
+    galaxy_routes: &GalaxyRoutes,
+    itinerary: &Itinerary,
+) -> f64 {
+    evaluate_itinerary(hunter_planning, galaxy_routes, itinerary, &HashMap::new()).0
+}
+
+/// Probability of being captured when meeting successively bounty hunters with the given capture probabilities.
+/// The Millennium Falcon can only be captured by bounty hunters if it escaped the previous ones,
+/// so for a constant 10% this gives 1/10 + 9/100 + 81/1000 + ...
+fn probability_been_captured(capture_probabilities: &[f64]) -> f64 {
+    let mut survival = 1.;
+    let mut r = 0.;
+    for capture_probability in capture_probabilities {
+        r += survival * capture_probability;
+        survival *= 1. - capture_probability;
+    }
+    r
+}
+
+#[cfg(test)]
+mod test {
+
+    use std::collections::{HashMap, HashSet};
+
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::{
+        domain_models::{
+            Action, BountyHunterPlanning, Departure, EmpireCaptureModel, EncounterDays,
+            EncounterPolicy, GalaxyRoutes, GeometricCaptureModel, Itinerary, Mission, Patrol,
+            PlanetCatalog, Pursuit, RefuelModel, Sighting, Step, Waypoint,
+        },
+        domain_services::{probability_been_captured, simulator::simulate_itinerary},
+    };
+
+    use super::{
+        compute_best_mission_plans, compute_itinerary_probability_of_success,
+        compute_pareto_frontier, compute_probability_of_success,
+    };
+
+    #[test]
+    fn test_probability_been_captured() {
+        assert_eq!(probability_been_captured(&[]), 0.);
+        assert_eq!(probability_been_captured(&[0.1]), 0.1);
+        assert_eq!(probability_been_captured(&[0.1; 2]), 0.19);
+        assert_eq!(probability_been_captured(&[0.1; 3]), 0.271);
+        assert_eq!(probability_been_captured(&[0.5, 0.2]), 0.6);
+    }
+
+    #[test]
+    fn test_compute_probability_of_success_simple() {
+        let planet_id_map = PlanetCatalog::from_vec(vec![
+            "Tatooine".to_string(),
+            "Dagobah".to_string(),
+            "Endor".to_string(),
+        ])
+        .unwrap();
+
+        let tatooine_id = *planet_id_map.get("Tatooine").unwrap();
+        let dagobah_id = *planet_id_map.get("Dagobah").unwrap();
+        let endor_id = *planet_id_map.get("Endor").unwrap();
+
+        let hunter_planning =
+            BountyHunterPlanning::new([(dagobah_id, HashSet::from([1]))].into_iter().collect());
+        let galaxy_routes = GalaxyRoutes::from_hashmap(HashMap::from([
+            (tatooine_id, vec![(dagobah_id, 1)]),
+            (dagobah_id, vec![(tatooine_id, 1), (endor_id, 1)]),
+            (endor_id, vec![(dagobah_id, 1)]),
+        ]))
+        .unwrap();
+
+        let r = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &Mission {
+                autonomy: 2,
+                departures: vec![Departure {
+                    planet: "Tatooine".to_string(),
+                    start_day: 0,
+                }],
+                arrivals: vec!["Endor".to_string()],
+                countdown: 2,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .probability_of_success;
+        assert_eq!(r, 0.9);
+    }
+
+    #[test]
+    fn test_compute_probability_of_success_directed_routes() {
+        let planet_id_map = PlanetCatalog::from_vec(vec![
+            "Tatooine".to_string(),
+            "Dagobah".to_string(),
+            "Endor".to_string(),
+        ])
+        .unwrap();
+
+        let tatooine_id = *planet_id_map.get("Tatooine").unwrap();
+        let dagobah_id = *planet_id_map.get("Dagobah").unwrap();
+        let endor_id = *planet_id_map.get("Endor").unwrap();
+
+        // one-way routes going around Tatooine -> Dagobah -> Endor -> Tatooine
+        let galaxy_routes = GalaxyRoutes::from_hashmap(HashMap::from([
+            (tatooine_id, vec![(dagobah_id, 1)]),
+            (dagobah_id, vec![(endor_id, 1)]),
+            (endor_id, vec![(tatooine_id, 1)]),
+        ]))
+        .unwrap();
+        let hunter_planning = BountyHunterPlanning::new(HashMap::new());
+
+        let mut mission = Mission {
+            autonomy: 2,
+            departures: vec![Departure {
+                planet: "Tatooine".to_string(),
+                start_day: 0,
+            }],
+            arrivals: vec!["Endor".to_string()],
+            countdown: 2,
+            ..Default::default()
+        };
+        let mission_plan = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &mission,
+        )
+        .unwrap();
+        assert_eq!(mission_plan.probability_of_success, 1.);
+
+        // the direct route from Endor to Dagobah doesn't exist
+        mission.departures[0].planet = "Endor".to_string();
+        mission.arrivals = vec!["Dagobah".to_string()];
+        let mission_plan = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &mission,
+        )
+        .unwrap();
+        assert_eq!(mission_plan.probability_of_success, 1.);
+        assert_eq!(mission_plan.itinerary.unwrap().arrival_day(), Some(2));
+
+        mission.countdown = 1;
+        let mission_plan = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &mission,
+        )
+        .unwrap();
+        assert_eq!(mission_plan.probability_of_success, 0.);
+    }
+
+    #[test]
+    fn test_compute_probability_of_success() {
+        let planet_id_map = PlanetCatalog::from_vec(vec![
+            "Tatooine".to_string(),
+            "Dagobah".to_string(),
+            "Endor".to_string(),
+            "Hoth".to_string(),
+        ])
+        .unwrap();
+
+        let tatooine_id = *planet_id_map.get("Tatooine").unwrap();
+        let dagobah_id = *planet_id_map.get("Dagobah").unwrap();
+        let endor_id = *planet_id_map.get("Endor").unwrap();
+        let hoth_id = *planet_id_map.get("Hoth").unwrap();
+
+        let hunter_planning = BountyHunterPlanning::new(
+            [(hoth_id, [6, 7, 8].into_iter().collect())]
+                .into_iter()
+                .collect(),
+        );
+
+        let galaxy_routes = GalaxyRoutes::from_hashmap(
+            [
+                (tatooine_id, vec![(dagobah_id, 6), (hoth_id, 6)]),
+                (
+                    dagobah_id,
+                    vec![(tatooine_id, 6), (endor_id, 4), (hoth_id, 1)],
+                ),
+                (endor_id, vec![(dagobah_id, 4), (hoth_id, 1)]),
+                (
+                    hoth_id,
+                    vec![(dagobah_id, 1), (endor_id, 1), (tatooine_id, 6)],
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        )
+        .unwrap();
+
+        let r = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &get_example_mission(7),
+        )
+        .unwrap()
+        .probability_of_success;
+        assert_eq!(r, 0.);
+
+        let r = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &get_example_mission(8),
+        )
+        .unwrap()
+        .probability_of_success;
+        assert_eq!(r, 0.81);
+
+        let r = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &get_example_mission(9),
+        )
+        .unwrap()
+        .probability_of_success;
+        assert_eq!(r, 0.9);
+
+        let r = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &get_example_mission(10),
+        )
+        .unwrap()
+        .probability_of_success;
+        assert_eq!(r, 1.)
+    }
+
+    #[test]
+    fn test_compute_probability_of_success_itinerary() {
+        let (planet_id_map, galaxy_routes, hunter_planning) = get_example_universe();
+        let tatooine_id = *planet_id_map.get("Tatooine").unwrap();
+        let endor_id = *planet_id_map.get("Endor").unwrap();
+        let hoth_id = *planet_id_map.get("Hoth").unwrap();
+
+        let mission_plan = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &get_example_mission(8),
+        )
+        .unwrap();
+        assert_eq!(mission_plan.probability_of_success, 0.81);
+        let itinerary = mission_plan.itinerary.unwrap();
+        assert_eq!(
+            itinerary.steps(),
+            [
+                Step {
+                    planet: tatooine_id,
+                    day: 0,
+                    fuel: 6,
+                    action: Action::Departure,
+                    bounty_hunter: false,
+                },
+                Step {
+                    planet: hoth_id,
+                    day: 6,
+                    fuel: 0,
+                    action: Action::Travel,
+                    bounty_hunter: true,
+                },
+                Step {
+                    planet: hoth_id,
+                    day: 7,
+                    fuel: 6,
+                    action: Action::Refuel,
+                    bounty_hunter: true,
+                },
+                Step {
+                    planet: endor_id,
+                    day: 8,
+                    fuel: 5,
+                    action: Action::Travel,
+                    bounty_hunter: false,
+                },
+            ]
+        );
+        assert_eq!(itinerary.arrival_day(), Some(8));
+        assert_eq!(itinerary.n_bounty_hunter(), 2);
+
+        let mission_plan = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &get_example_mission(7),
+        )
+        .unwrap();
+        assert_eq!(mission_plan.itinerary, None);
+    }
+
+    #[test]
+    fn test_compute_best_mission_plans() {
+        let (planet_id_map, galaxy_routes, hunter_planning) = get_example_universe();
+        let mut mission = get_example_mission(10);
+
+        let mission_plans = compute_best_mission_plans(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &mission,
+            4,
+        )
+        .unwrap();
+        let odds_and_arrival_days = mission_plans
+            .iter()
+            .map(|mission_plan| {
+                (
+                    mission_plan.probability_of_success,
+                    mission_plan
+                        .itinerary
+                        .as_ref()
+                        .unwrap()
+                        .arrival_day()
+                        .unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            odds_and_arrival_days,
+            [(1., 10), (1., 10), (0.9, 9), (0.9, 10)]
+        );
+        assert_ne!(mission_plans[0], mission_plans[1]);
+
+        // only a single itinerary is possible with a countdown of 8
+        mission.countdown = 8;
+        let mission_plans = compute_best_mission_plans(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &mission,
+            4,
+        )
+        .unwrap();
+        assert_eq!(mission_plans.len(), 1);
+        assert_eq!(mission_plans[0].probability_of_success, 0.81);
+    }
+
+    #[test]
+    fn test_compute_pareto_frontier() {
+        let (planet_id_map, galaxy_routes, hunter_planning) = get_example_universe();
+        let mission = get_example_mission(10);
+
+        let pareto_frontier =
+            compute_pareto_frontier(&hunter_planning, &galaxy_routes, &planet_id_map, &mission)
+                .unwrap();
+        let frontier = pareto_frontier
+            .iter()
+            .map(|mission_plan| {
+                let itinerary = mission_plan.itinerary.as_ref().unwrap();
+                (
+                    itinerary.arrival_day().unwrap(),
+                    itinerary.n_bounty_hunter(),
+                    mission_plan.probability_of_success,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(frontier, [(10, 0, 1.), (9, 1, 0.9), (8, 2, 0.81)]);
+    }
+
+    #[test]
+    fn test_compute_with_large_countdown() {
+        let (planet_id_map, galaxy_routes, hunter_planning) = get_example_universe();
+        let mut mission = get_example_mission(1_000_000_000);
+
+        let mission_plan = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &mission,
+        )
+        .unwrap();
+        assert_eq!(mission_plan.probability_of_success, 1.);
+        assert_eq!(mission_plan.itinerary.unwrap().arrival_day(), Some(10));
+
+        let pareto_frontier =
+            compute_pareto_frontier(&hunter_planning, &galaxy_routes, &planet_id_map, &mission)
+                .unwrap();
+        assert_eq!(pareto_frontier.len(), 3);
+
+        // the arrival planet can't be reached, whatever the countdown
+        mission.autonomy = 5;
+        let mission_plan = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &mission,
+        )
+        .unwrap();
+        assert_eq!(mission_plan.probability_of_success, 0.);
+
+        // the last event is now the end of the blockade of Tatooine, on day 20
+        let mut hunter_planning = hunter_planning;
+        let tatooine_id = *planet_id_map.get("Tatooine").unwrap();
+        let dagobah_id = *planet_id_map.get("Dagobah").unwrap();
+        let hoth_id = *planet_id_map.get("Hoth").unwrap();
+        hunter_planning.add_route_closure(tatooine_id, dagobah_id, 0, 20);
+        hunter_planning.add_route_closure(tatooine_id, hoth_id, 0, 20);
+        mission.autonomy = 6;
+        let mission_plan = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &mission,
+        )
+        .unwrap();
+        assert_eq!(mission_plan.probability_of_success, 1.);
+        assert_eq!(mission_plan.itinerary.unwrap().arrival_day(), Some(29));
+
+        mission.autonomy = 5;
+        let mission_plan = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &mission,
+        )
+        .unwrap();
+        assert_eq!(mission_plan.probability_of_success, 0.);
+    }
+
+    #[test]
+    fn test_compute_probability_of_success_route_closures() {
+        let (planet_id_map, galaxy_routes, mut hunter_planning) = get_example_universe();
+        let tatooine_id = *planet_id_map.get("Tatooine").unwrap();
+        let dagobah_id = *planet_id_map.get("Dagobah").unwrap();
+        let hoth_id = *planet_id_map.get("Hoth").unwrap();
+
+        // the Millennium Falcon can't leave Tatooine for Dagobah on the first day anymore
+        hunter_planning.add_route_closure(tatooine_id, dagobah_id, 0, 0);
+        let r = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &get_example_mission(9),
+        )
+        .unwrap()
+        .probability_of_success;
+        assert_eq!(r, 0.81);
+
+        // closing the route from Hoth to Dagobah leaves the one from Dagobah to Hoth usable
+        hunter_planning.add_route_closure(hoth_id, dagobah_id, 7, 8);
+        let mission_plan = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &get_example_mission(10),
+        )
+        .unwrap();
+        assert_eq!(mission_plan.probability_of_success, 1.);
+        assert!(hunter_planning.is_route_closed(&hoth_id, &dagobah_id, 8));
+        assert!(!hunter_planning.is_route_closed(&dagobah_id, &hoth_id, 8));
+
+        // but the Millennium Falcon can't go from Dagobah to Hoth on days 7 and 8 anymore once it is closed too
+        hunter_planning.add_route_closure(dagobah_id, hoth_id, 7, 8);
+        let mission_plan = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &get_example_mission(10),
+        )
+        .unwrap();
+        assert_eq!(mission_plan.probability_of_success, 0.9);
+        assert!(hunter_planning.is_route_closed(&dagobah_id, &hoth_id, 8));
+        assert!(!hunter_planning.is_route_closed(&dagobah_id, &hoth_id, 9));
+    }
+
+    #[test]
+    fn test_compute_probability_of_success_pursuit() {
+        let (planet_id_map, galaxy_routes, mut hunter_planning) = get_example_universe();
+        hunter_planning.set_pursuit(Pursuit {
+            capture_probability: 0.1,
+        });
+
+        // spotted on Hoth on day 7, the Millennium Falcon meets the pursuers arriving on Endor on day 8
+        let mission_plan = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &get_example_mission(8),
+        )
+        .unwrap();
+        assert_eq!(mission_plan.probability_of_success, 0.729);
+        let itinerary = mission_plan.itinerary.unwrap();
+        assert_eq!(itinerary.n_bounty_hunter(), 3);
+        assert_eq!(
+            compute_itinerary_probability_of_success(&hunter_planning, &galaxy_routes, &itinerary),
+            0.729
+        );
+
+        // going through Dagobah, it is spotted on Hoth on day 8 and the pursuers are on Endor on day 9
+        let r = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &get_example_mission(9),
+        )
+        .unwrap()
+        .probability_of_success;
+        assert_eq!(r, 0.81);
+
+        // when it is never spotted, nobody goes after it
+        let r = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &get_example_mission(10),
+        )
+        .unwrap()
+        .probability_of_success;
+        assert_eq!(r, 1.);
+    }
+
+    #[test]
+    fn test_compute_with_large_countdown_and_endless_patrol() {
+        let (planet_id_map, galaxy_routes, mut hunter_planning) = get_example_universe();
+        let hoth_id = *planet_id_map.get("Hoth").unwrap();
+        // bounty hunters patrol Hoth every other day, forever
+        hunter_planning.add_patrol(
+            hoth_id,
+            Patrol {
+                first_day: 6,
+                last_day: None,
+                period: 2,
+                sighting: Sighting {
+                    presence_probability: 1.,
+                    capture_probability: 0.1,
+                },
+            },
+        );
+        let mut mission = get_example_mission(1_000_000_000);
+
+        // the Millennium Falcon can still avoid the bounty hunters, waiting for them to leave
+        let mission_plan = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &mission,
+        )
+        .unwrap();
+        assert_eq!(mission_plan.probability_of_success, 1.);
+        let itinerary = mission_plan.itinerary.unwrap();
+        assert_eq!(itinerary.n_bounty_hunter(), 0);
+
+        // the same day in the cycle of the patrol is the same situation, so the search ends whatever the countdown
+        mission.autonomy = 5;
+        let mission_plan = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &mission,
+        )
+        .unwrap();
+        assert_eq!(mission_plan.probability_of_success, 0.);
+
+        // with another patrol, the cycle doesn't fit in a u64 anymore: the day can't be dropped,
+        // and the search explores every day until the end of a smaller countdown
+        hunter_planning.add_patrol(
+            hoth_id,
+            Patrol {
+                first_day: 6,
+                last_day: None,
+                period: u64::MAX,
+                sighting: Sighting {
+                    presence_probability: 1.,
+                    capture_probability: 0.1,
+                },
+            },
+        );
+        assert_eq!(hunter_planning.patrol_cycle(), None);
+        mission.countdown = 10_000;
+        let mission_plan = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &mission,
+        )
+        .unwrap();
+        assert_eq!(mission_plan.probability_of_success, 0.);
+        mission.autonomy = 6;
+        let mission_plan = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &mission,
+        )
+        .unwrap();
+        assert_eq!(mission_plan.probability_of_success, 1.);
+    }
+
+    #[test]
+    fn test_compute_with_large_countdown_and_pursuit() {
+        let planet_id_map = PlanetCatalog::from_vec(vec![
+            "Tatooine".to_string(),
+            "Dagobah".to_string(),
+            "Hoth".to_string(),
+            "Endor".to_string(),
+        ])
+        .unwrap();
+        let tatooine_id = *planet_id_map.get("Tatooine").unwrap();
+        let dagobah_id = *planet_id_map.get("Dagobah").unwrap();
+        let hoth_id = *planet_id_map.get("Hoth").unwrap();
+        let endor_id = *planet_id_map.get("Endor").unwrap();
+
+        // Endor is too far for the autonomy, but the Millennium Falcon can go around Tatooine, Dagobah and Hoth
+        // meeting the pursuers sent after it forever
+        let mut galaxy_routes = GalaxyRoutes::new();
+        galaxy_routes.add_route(tatooine_id, dagobah_id, 1);
+        galaxy_routes.add_route(tatooine_id, hoth_id, 1);
+        galaxy_routes.add_route(dagobah_id, hoth_id, 3);
+        galaxy_routes.add_route(hoth_id, endor_id, 3);
+        let mut hunter_planning = BountyHunterPlanning::new(
+            [(tatooine_id, [0].into_iter().collect())]
+                .into_iter()
+                .collect(),
+        );
+        hunter_planning.set_pursuit(Pursuit {
+            capture_probability: 0.1,
+        });
+        let mission = Mission {
+            autonomy: 2,
+            departures: vec![Departure {
+                planet: "Tatooine".to_string(),
+                start_day: 0,
+            }],
+            arrivals: vec!["Endor".to_string()],
+            countdown: 1_000_000_000,
+            ..Default::default()
+        };
+
+        // the same pursuers coming later are the same situation, so the search ends whatever the countdown
+        let mission_plan = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &mission,
+        )
+        .unwrap();
+        assert_eq!(mission_plan.probability_of_success, 0.);
+        let mission_plans = compute_best_mission_plans(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &mission,
+            3,
+        )
+        .unwrap();
+        assert!(mission_plans.is_empty());
+    }
+
+    #[test]
+    fn test_compute_probability_of_success_encounter_policy() {
+        let (planet_id_map, galaxy_routes, mut hunter_planning) = get_example_universe();
+
+        // the Millennium Falcon arrives on Hoth on day 6 and refuels there on day 7, with bounty hunters both days
+        let policies = [
+            (EncounterDays::PresentAndRefuel, false, 0.81),
+            (EncounterDays::Present, false, 0.9),
+            (EncounterDays::Arrival, false, 0.9),
+            (EncounterDays::PresentAndRefuel, true, 0.9),
+        ];
+        for (days, consecutive_days_once, expected) in policies {
+            hunter_planning.set_encounter_policy(EncounterPolicy {
+                days,
+                consecutive_days_once,
+            });
+            let mission_plan = compute_probability_of_success(
+                &hunter_planning,
+                &galaxy_routes,
+                &planet_id_map,
+                &get_example_mission(8),
+            )
+            .unwrap();
+            assert_eq!(mission_plan.probability_of_success, expected);
+            assert_eq!(
+                compute_itinerary_probability_of_success(
+                    &hunter_planning,
+                    &galaxy_routes,
+                    &mission_plan.itinerary.unwrap()
+                ),
+                expected
+            );
+        }
+
+        // staying on Hoth from day 6 to day 8 only meets the bounty hunters once
+        let steps = [
+            (Action::Departure, "Tatooine", 0),
+            (Action::Travel, "Hoth", 6),
+            (Action::Refuel, "Hoth", 7),
+            (Action::Wait, "Hoth", 8),
+            (Action::Travel, "Endor", 9),
+        ];
+        let itinerary = Itinerary::new(
+            steps
+                .into_iter()
+                .map(|(action, planet, day)| Step {
+                    planet: *planet_id_map.get(planet).unwrap(),
+                    day,
+                    fuel: 0,
+                    action,
+                    bounty_hunter: false,
+                })
+                .collect(),
+        );
+        assert_eq!(
+            compute_itinerary_probability_of_success(&hunter_planning, &galaxy_routes, &itinerary),
+            0.9
+        );
+        hunter_planning.set_encounter_policy(EncounterPolicy::default());
+        assert_eq!(
+            compute_itinerary_probability_of_success(&hunter_planning, &galaxy_routes, &itinerary),
+            0.729
+        );
+    }
+
+    #[test]
+    fn test_compute_probability_of_success_forbidden_planets() {
+        let (planet_id_map, galaxy_routes, hunter_planning) = get_example_universe();
+        let mut mission = get_example_mission(10);
+        mission.forbidden_planets = vec!["Hoth".to_string()];
+
+        let r = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &mission,
+        )
+        .unwrap()
+        .probability_of_success;
+        assert_eq!(r, 0.);
+
+        mission.countdown = 11;
+        let mission_plan = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &mission,
+        )
+        .unwrap();
+        assert_eq!(mission_plan.probability_of_success, 1.);
+        let hoth_id = *planet_id_map.get("Hoth").unwrap();
+        assert!(mission_plan
+            .itinerary
+            .unwrap()
+            .steps()
+            .iter()
+            .all(|step| step.planet != hoth_id));
+    }
+
+    #[test]
+    fn test_compute_probability_of_success_waypoints() {
+        let (planet_id_map, galaxy_routes, hunter_planning) = get_example_universe();
+        let mut mission = get_example_mission(10);
+        let compute = |mission: &Mission| {
+            compute_probability_of_success(
+                &hunter_planning,
+                &galaxy_routes,
+                &planet_id_map,
+                mission,
+            )
+            .unwrap()
+            .probability_of_success
+        };
+
+        mission.waypoints = vec![Waypoint {
+            planet: "Hoth".to_string(),
+            deadline: None,
+        }];
+        assert_eq!(compute(&mission), 1.);
+
+        // Hoth has to be visited while the bounty hunters are there
+        mission.waypoints[0].deadline = Some(7);
+        assert_eq!(compute(&mission), 0.81);
+
+        mission.waypoints = vec![
+            Waypoint {
+                planet: "Hoth".to_string(),
+                deadline: None,
+            },
+            Waypoint {
+                planet: "Dagobah".to_string(),
+                deadline: None,
+            },
+        ];
+        assert_eq!(compute(&mission), 1.);
+
+        // going to Dagobah after Hoth takes too long to avoid the bounty hunters
+        mission.ordered_waypoints = true;
+        assert_eq!(compute(&mission), 0.81);
+
+        mission.waypoints[1].planet = "Coruscant".to_string();
+        assert_eq!(compute(&mission), 0.);
+    }
+
+    #[test]
+    fn test_compute_probability_of_success_several_departures_and_arrivals() {
+        let (planet_id_map, galaxy_routes, hunter_planning) = get_example_universe();
+        let dagobah_id = *planet_id_map.get("Dagobah").unwrap();
+        let endor_id = *planet_id_map.get("Endor").unwrap();
+        let mut mission = get_example_mission(7);
+        let compute = |mission: &Mission| {
+            compute_probability_of_success(
+                &hunter_planning,
+                &galaxy_routes,
+                &planet_id_map,
+                mission,
+            )
+            .unwrap()
+        };
+        assert_eq!(compute(&mission).probability_of_success, 0.);
+
+        // the Millennium Falcon can also leave from Dagobah, but not before day 3
+        mission.departures.push(Departure {
+            planet: "Dagobah".to_string(),
+            start_day: 3,
+        });
+        let mission_plan = compute(&mission);
+        assert_eq!(mission_plan.probability_of_success, 1.);
+        let itinerary = mission_plan.itinerary.unwrap();
+        assert_eq!(itinerary.departure_planet(), Some(&dagobah_id));
+        assert_eq!(itinerary.arrival_planet(), Some(&endor_id));
+        assert_eq!(itinerary.arrival_day(), Some(5));
+
+        // or deliver to Hoth, which is closer
+        mission.arrivals.push("Hoth".to_string());
+        let mission_plan = compute(&mission);
+        assert_eq!(mission_plan.probability_of_success, 1.);
+        assert_eq!(mission_plan.itinerary.unwrap().arrival_day(), Some(4));
+
+        // leaving Dagobah later, the Millennium Falcon meets the bounty hunters of Hoth
+        mission.departures[1].start_day = 5;
+        let mission_plan = compute(&mission);
+        assert_eq!(mission_plan.probability_of_success, 0.9);
+        assert_eq!(mission_plan.itinerary.unwrap().arrival_day(), Some(6));
+    }
+
+    #[test]
+    fn test_compute_probability_of_success_without_fuel_depot() {
+        let (mut planet_id_map, galaxy_routes, hunter_planning) = get_example_universe();
+        let hoth_id = *planet_id_map.get("Hoth").unwrap();
+        planet_id_map.set_fuel_depot(&hoth_id, false);
+        let mut mission = get_example_mission(8);
+
+        // the Millennium Falcon can't refuel on Hoth anymore, so can't jump from Hoth to Endor
+        let r = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &mission,
+        )
+        .unwrap()
+        .probability_of_success;
+        assert_eq!(r, 0.);
+
+        // it needs to refuel on Dagobah
+        mission.countdown = 9;
+        let mission_plan = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &mission,
+        )
+        .unwrap();
+        assert_eq!(mission_plan.probability_of_success, 0.9);
+        let planets = mission_plan
+            .itinerary
+            .unwrap()
+            .steps()
+            .iter()
+            .map(|step| planet_id_map.name(&step.planet).unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(planets, ["Tatooine", "Dagobah", "Dagobah", "Hoth", "Endor"]);
+    }
+
+    #[test]
+    fn test_compute_probability_of_success_refuel_model() {
+        let (planet_id_map, galaxy_routes, hunter_planning) = get_example_universe();
+        let hoth_id = *planet_id_map.get("Hoth").unwrap();
+
+        // refuelling on Hoth now takes 2 days, so the Millennium Falcon arrives a day later
+        let mut mission = get_example_mission(9);
+        mission.refuel_model = RefuelModel::Full { days: 2 };
+        let mission_plan = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &mission,
+        )
+        .unwrap();
+        assert_eq!(mission_plan.probability_of_success, 0.729);
+        let fuel_on_hoth = mission_plan
+            .itinerary
+            .unwrap()
+            .steps()
+            .iter()
+            .filter(|step| step.planet == hoth_id)
+            .map(|step| (step.day, step.fuel))
+            .collect::<Vec<_>>();
+        assert_eq!(fuel_on_hoth, [(6, 0), (7, 0), (8, 6)]);
+
+        // a single day of partial refuel is enough to jump from Hoth to Endor
+        mission.countdown = 8;
+        mission.refuel_model = RefuelModel::Partial { fuel_per_day: 1 };
+        let mission_plan = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &mission,
+        )
+        .unwrap();
+        assert_eq!(mission_plan.probability_of_success, 0.81);
+        let last_step = mission_plan
+            .itinerary
+            .unwrap()
+            .steps()
+            .last()
+            .unwrap()
+            .clone();
+        assert_eq!((last_step.day, last_step.fuel), (8, 0));
+    }
+
+    #[test]
+    fn test_compute_probability_of_success_capture_model() {
+        let (planet_id_map, galaxy_routes, _) = get_example_universe();
+        let hoth_id = *planet_id_map.get("Hoth").unwrap();
+
+        // the bounty hunter on Hoth on day 8 is much more dangerous than the others
+        let capture_model = EmpireCaptureModel {
+            hunters: HashMap::from([((hoth_id, 8), 0.5)]),
+            ..Default::default()
+        };
+        let hunter_planning = BountyHunterPlanning::with_capture_model(
+            HashMap::from([(hoth_id, HashSet::from([6, 7, 8]))]),
+            &capture_model,
+        );
+
+        // meeting 2 bounty hunters on day 6 and 7 is safer than meeting the one of day 8
+        let mission_plan = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &get_example_mission(9),
+        )
+        .unwrap();
+        assert_eq!(mission_plan.probability_of_success, 0.81);
+        assert_eq!(mission_plan.itinerary.unwrap().n_bounty_hunter(), 2);
+    }
+
+    #[test]
+    fn test_compute_probability_of_success_certain_capture() {
+        let (planet_id_map, galaxy_routes, _) = get_example_universe();
+        let hoth_id = *planet_id_map.get("Hoth").unwrap();
+
+        let capture_model = EmpireCaptureModel {
+            planets: HashMap::from([(hoth_id, 1.)]),
+            ..Default::default()
+        };
+        let hunter_planning = BountyHunterPlanning::with_capture_model(
+            HashMap::from([(hoth_id, HashSet::from([6, 7, 8]))]),
+            &capture_model,
+        );
+
+        // the only itinerary meets a bounty hunter that can't be escaped
+        let mission_plan = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &get_example_mission(9),
+        )
+        .unwrap();
+        assert_eq!(mission_plan.probability_of_success, 0.);
+        assert!(mission_plan.itinerary.is_some());
+
+        // with more time, the Millennium Falcon waits for the bounty hunters to leave Hoth
+        let pareto_frontier = compute_pareto_frontier(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &get_example_mission(10),
+        )
+        .unwrap()
+        .iter()
+        .map(|mission_plan| mission_plan.probability_of_success)
+        .collect::<Vec<_>>();
+        assert_eq!(pareto_frontier, [1., 0.]);
+    }
+
+    #[test]
+    fn test_compute_probability_of_success_presence_probability() {
+        let (planet_id_map, galaxy_routes, _) = get_example_universe();
+        let hoth_id = *planet_id_map.get("Hoth").unwrap();
+
+        // the Rebellion is only 50% sure of the bounty hunters of Hoth on days 6 and 7
+        let hunter_planning = BountyHunterPlanning::with_presence_probabilities(
+            HashMap::from([(hoth_id, HashMap::from([(6, 0.5), (7, 0.5), (8, 1.)]))]),
+            &GeometricCaptureModel::default(),
+        );
+
+        let r = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &get_example_mission(8),
+        )
+        .unwrap()
+        .probability_of_success;
+        assert_eq!(r, 0.9025);
+
+        // now going through Hoth on days 6 and 7 is safer than on day 8
+        let r = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &get_example_mission(9),
+        )
+        .unwrap()
+        .probability_of_success;
+        assert_eq!(r, 0.9025);
+
+        // only the bounty hunters really there send pursuers, so the pursuers sent from Hoth on day 7
+        // are on Endor on day 8 half of the time
+        let mut hunter_planning = hunter_planning;
+        hunter_planning.set_pursuit(Pursuit {
+            capture_probability: 0.1,
+        });
+        let mission_plan = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &get_example_mission(8),
+        )
+        .unwrap();
+        let expected = (0.5 + 0.5 * 0.9) * (0.5 + 0.5 * 0.9 * 0.9);
+        assert!((mission_plan.probability_of_success - expected).abs() < 1e-10);
+        let itinerary = mission_plan.itinerary.unwrap();
+        // the bounty hunters may be met on Hoth on days 6 and 7, and the pursuers on Endor on day 8
+        assert_eq!(itinerary.n_bounty_hunter(), 3);
+
+        // when consecutive days count once, the bounty hunters of day 7 are only met if those of day 6
+        // were not there, as in the simulation of the itinerary
+        let mut hunter_planning = BountyHunterPlanning::with_presence_probabilities(
+            HashMap::from([(hoth_id, HashMap::from([(6, 0.5), (7, 0.5)]))]),
+            &GeometricCaptureModel::default(),
+        );
+        hunter_planning.set_encounter_policy(EncounterPolicy {
+            days: EncounterDays::PresentAndRefuel,
+            consecutive_days_once: true,
+        });
+        let mission_plan = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &get_example_mission(8),
+        )
+        .unwrap();
+        let r = mission_plan.probability_of_success;
+        assert!((r - (1. - 0.1 * (0.5 + 0.5 * 0.5))).abs() < 1e-10);
+        let simulation = simulate_itinerary(
+            &hunter_planning,
+            &galaxy_routes,
+            &mission_plan.itinerary.unwrap(),
+            100_000,
+            &mut StdRng::seed_from_u64(42),
+        );
+        let (low, high) = simulation.confidence_interval;
+        assert!(low <= r && r <= high);
+    }
+
+    /// universe of the examples: planets, routes and the bounty hunters planning of the empire
+    pub(crate) fn get_example_universe() -> (PlanetCatalog, GalaxyRoutes, BountyHunterPlanning) {
+        let planet_id_map = PlanetCatalog::from_vec(vec![
+            "Tatooine".to_string(),
+            "Dagobah".to_string(),
+            "Endor".to_string(),
+            "Hoth".to_string(),
+        ])
+        .unwrap();
+
+        let tatooine_id = *planet_id_map.get("Tatooine").unwrap();
+        let dagobah_id = *planet_id_map.get("Dagobah").unwrap();
+        let endor_id = *planet_id_map.get("Endor").unwrap();
+        let hoth_id = *planet_id_map.get("Hoth").unwrap();
+
+        let hunter_planning = BountyHunterPlanning::new(
+            [(hoth_id, [6, 7, 8].into_iter().collect())]
+                .into_iter()
+                .collect(),
+        );
+
+        let mut galaxy_routes = GalaxyRoutes::new();
+        galaxy_routes.add_route(tatooine_id, dagobah_id, 6);
+        galaxy_routes.add_route(dagobah_id, endor_id, 4);
+        galaxy_routes.add_route(dagobah_id, hoth_id, 1);
+        galaxy_routes.add_route(hoth_id, endor_id, 1);
+        galaxy_routes.add_route(tatooine_id, hoth_id, 6);
+
+        (planet_id_map, galaxy_routes, hunter_planning)
+    }
+
+    /// mission of the examples, going from Tatooine to Endor with an autonomy of 6
+    pub(crate) fn get_example_mission(countdown: u64) -> Mission {
+        Mission {
+            autonomy: 6,
+            departures: vec![Departure {
+                planet: "Tatooine".to_string(),
+                start_day: 0,
+            }],
+            arrivals: vec!["Endor".to_string()],
+            countdown,
+            ..Default::default()
+        }
+    }
+}
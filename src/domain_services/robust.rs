@@ -0,0 +1,619 @@
+use std::collections::{BinaryHeap, HashMap};
+
+use anyhow::{anyhow, Result};
+
+use crate::domain_models::{
+    Action, BountyHunterPlanning, GalaxyRoutes, Itinerary, Mission, PlanetCatalog, PlanetId,
+    RobustObjective, RobustPlan, Scenario, Step,
+};
+
+use super::{evaluate_itinerary, Hypothesis, Risk, SearchContext};
+
+/// Find the single itinerary maximizing the expected or worst-case odds of the mission over several scenarios
+/// of the Empire. The countdown of the mission is replaced by the one of each scenario, and an itinerary has
+/// no chance of success in the scenarios where it arrives too late or takes a closed route.
+/// Ties are broken by the other objective, then by the arrival day.
+///
+/// A single best-first search follows the Millennium Falcon in all the scenarios at once: a node holds the risk
+/// of each hypothesis of each scenario. The risks only grow along an itinerary, so both objectives computed from
+/// the current risks can only decrease, and the first itinerary reaching the arrival planet is the best one.
+/// A node is skipped when another one at the same position is at least as safe in every hypothesis
+/// of every scenario.
+pub fn compute_robust_mission_plan(
+    scenarios: &[Scenario],
+    galaxy_routes: &GalaxyRoutes,
+    planet_id_map: &PlanetCatalog,
+    mission: &Mission,
+    objective: RobustObjective,
+) -> Result<RobustPlan> {
+    let total_weight = scenarios
+        .iter()
+        .map(|scenario| scenario.weight)
+        .sum::<f64>();
+    if scenarios.iter().any(|scenario| scenario.weight < 0.) || total_weight <= 0. {
+        return Err(anyhow!(
+            "the weights of the scenarios should be positive, and at least one of them not null"
+        ));
+    }
+
+    let no_plan = RobustPlan {
+        itinerary: None,
+        bounty_hunters: vec![Vec::new(); scenarios.len()],
+        probabilities_of_success: vec![0.; scenarios.len()],
+        expected_probability_of_success: 0.,
+        worst_case_probability_of_success: 0.,
+    };
+    let mut search_contexts = Vec::new();
+    for scenario in scenarios {
+        // the search context doesn't depend on the countdown, which is checked by the robust search
+        match SearchContext::new(
+            &scenario.hunter_planning,
+            galaxy_routes,
+            planet_id_map,
+            mission,
+        )? {
+            Some(search_context) => search_contexts.push(search_context),
+            None => return Ok(no_plan),
+        }
+    }
+    let robust_search = RobustSearch {
+        scenarios,
+        search_contexts,
+        total_weight,
+        objective,
+    };
+    let Some(itinerary) = robust_search.search(mission.autonomy)? else {
+        return Ok(no_plan);
+    };
+
+    let (probabilities_of_success, bounty_hunters): (Vec<_>, Vec<_>) = scenarios
+        .iter()
+        .map(|scenario| {
+            let (probability_of_success, bounty_hunters) = evaluate_itinerary(
+                &scenario.hunter_planning,
+                galaxy_routes,
+                &itinerary,
+                &HashMap::new(),
+            );
+            if is_itinerary_possible(&scenario.hunter_planning, scenario.countdown, &itinerary) {
+                (probability_of_success, bounty_hunters)
+            } else {
+                (0., bounty_hunters)
+            }
+        })
+        .unzip();
+    let (expected_probability_of_success, worst_case_probability_of_success) =
+        robust_search.expected_and_worst_case(&probabilities_of_success);
+    Ok(RobustPlan {
+        itinerary: Some(itinerary),
+        bounty_hunters,
+        probabilities_of_success,
+        expected_probability_of_success,
+        worst_case_probability_of_success,
+    })
+}
+
+/// State of the Millennium Falcon in a scenario: the risk over all the hypotheses, and the one of each hypothesis
+#[derive(Debug, Clone)]
+struct ScenarioState {
+    risk: Risk,
+    hypotheses: Vec<(Hypothesis, Risk)>,
+}
+
+/// Node of the robust search. The position of the Millennium Falcon is the same in all the scenarios,
+/// only the bounty hunters it meets differ
+#[derive(Debug)]
+struct RobustNode {
+    elapsed_time: u64,
+    time_to_destination: u64,
+    fuel: u64,
+    refuelling: u64,
+    planet: PlanetId,
+    waypoints: u64,
+    /// state in each scenario, None when the itinerary can't succeed in this scenario anymore
+    scenario_states: Vec<Option<ScenarioState>>,
+    /// value of the objective and of the other objective, with the probability of success in each scenario
+    /// if no other bounty hunter is met
+    objectives: (f64, f64),
+    action: Action,
+    bounty_hunter: bool,
+    parent: Option<usize>,
+}
+
+/// Position of a robust node, with the hypotheses of each scenario
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct RobustPosition {
+    elapsed_time: u64,
+    fuel: u64,
+    refuelling: u64,
+    planet: PlanetId,
+    waypoints: u64,
+    scenarios: Vec<Option<Vec<Hypothesis>>>,
+}
+
+/// Position of a robust node without the day, with the pursuers counted from the day of the node
+/// and the day in the cycle of the patrols without end of each scenario,
+/// as the situation of the search of a single scenario
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct RobustSituation {
+    phases: Vec<u64>,
+    fuel: u64,
+    refuelling: u64,
+    planet: PlanetId,
+    waypoints: u64,
+    scenarios: Vec<Option<Vec<Hypothesis>>>,
+}
+
+impl RobustNode {
+    fn position(&self) -> RobustPosition {
+        RobustPosition {
+            elapsed_time: self.elapsed_time,
+            fuel: self.fuel,
+            refuelling: self.refuelling,
+            planet: self.planet,
+            waypoints: self.waypoints,
+            scenarios: self
+                .scenario_states
+                .iter()
+                .map(|state| {
+                    state.as_ref().map(|state| {
+                        state
+                            .hypotheses
+                            .iter()
+                            .map(|(hypothesis, _)| hypothesis.clone())
+                            .collect()
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// Probability of being in each hypothesis of each scenario without having been captured.
+    /// Nodes at the same position have the same hypotheses
+    fn hypothesis_survivals(&self) -> Vec<f64> {
+        self.scenario_states
+            .iter()
+            .flatten()
+            .flat_map(|state| state.hypotheses.iter().map(|(_, risk)| (-risk.0).exp()))
+            .collect()
+    }
+
+    fn situation(&self, patrol_cycles: &[u64]) -> RobustSituation {
+        RobustSituation {
+            phases: patrol_cycles
+                .iter()
+                .map(|patrol_cycle| self.elapsed_time % patrol_cycle)
+                .collect(),
+            fuel: self.fuel,
+            refuelling: self.refuelling,
+            planet: self.planet,
+            waypoints: self.waypoints,
+            scenarios: self
+                .scenario_states
+                .iter()
+                .map(|state| {
+                    state.as_ref().map(|state| {
+                        state
+                            .hypotheses
+                            .iter()
+                            .map(|(hypothesis, _)| hypothesis.relative_to(self.elapsed_time))
+                            .collect()
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+impl PartialEq for RobustNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for RobustNode {}
+
+impl PartialOrd for RobustNode {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for RobustNode {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        // the node with the best objectives should be explored first, then the one that can arrive the soonest
+        let sum_time = self.elapsed_time.saturating_add(self.time_to_destination);
+        let other_sum_time = other.elapsed_time.saturating_add(other.time_to_destination);
+        self.objectives
+            .0
+            .total_cmp(&other.objectives.0)
+            .then(self.objectives.1.total_cmp(&other.objectives.1))
+            .then(other_sum_time.cmp(&sum_time))
+    }
+}
+
+/// Return true if the survivals are at least as good as the other ones, in every scenario or hypothesis
+fn dominates(survivals: &[f64], other_survivals: &[f64]) -> bool {
+    survivals
+        .iter()
+        .zip(other_survivals)
+        .all(|(survival, other_survival)| survival >= other_survival)
+}
+
+struct RobustSearch<'a> {
+    scenarios: &'a [Scenario],
+    /// search context of each scenario. They only differ by the bounty hunters
+    search_contexts: Vec<SearchContext<'a>>,
+    total_weight: f64,
+    objective: RobustObjective,
+}
+
+impl RobustSearch<'_> {
+    /// Expected and worst-case odds, given the odds in each scenario
+    fn expected_and_worst_case(&self, probabilities_of_success: &[f64]) -> (f64, f64) {
+        let expected = self
+            .scenarios
+            .iter()
+            .zip(probabilities_of_success)
+            .map(|(scenario, p)| scenario.weight * p)
+            .sum::<f64>()
+            / self.total_weight;
+        let worst_case = probabilities_of_success
+            .iter()
+            .copied()
+            .fold(f64::INFINITY, f64::min);
+        (expected, worst_case)
+    }
+
+    /// Value of the objective and of the other objective, given the odds in each scenario
+    fn objectives(&self, probabilities_of_success: &[f64]) -> (f64, f64) {
+        let (expected, worst_case) = self.expected_and_worst_case(probabilities_of_success);
+        match self.objective {
+            RobustObjective::Expected => (expected, worst_case),
+            RobustObjective::WorstCase => (worst_case, expected),
+        }
+    }
+
+    /// Create the node reached by `action` from the states of `parent`, or None if the itinerary can't succeed
+    /// in any scenario anymore. `jump` is the planet and the day the Millennium Falcon leaves when travelling
+    #[allow(clippy::too_many_arguments)]
+    fn new_node(
+        &self,
+        parent: Option<(usize, &[Option<ScenarioState>])>,
+        planet: PlanetId,
+        elapsed_time: u64,
+        fuel: u64,
+        refuelling: u64,
+        waypoints: u64,
+        action: Action,
+        jump: Option<(PlanetId, u64)>,
+    ) -> Option<RobustNode> {
+        let departure_state = Some(ScenarioState {
+            risk: Risk(0.),
+            hypotheses: vec![(Hypothesis::default(), Risk(0.))],
+        });
+        let mut bounty_hunter = false;
+        let mut scenario_states = Vec::new();
+        for (i, (scenario, search_context)) in
+            self.scenarios.iter().zip(&self.search_contexts).enumerate()
+        {
+            let state = match parent {
+                Some((_, states)) => &states[i],
+                None => &departure_state,
+            };
+            let Some(state) = state else {
+                scenario_states.push(None);
+                continue;
+            };
+            if jump.is_some_and(|(origin, day)| {
+                scenario
+                    .hunter_planning
+                    .is_route_closed(&origin, &planet, day)
+            }) {
+                scenario_states.push(None);
+                continue;
+            }
+            let node = search_context.new_node(
+                None,
+                &state.hypotheses,
+                planet,
+                elapsed_time,
+                fuel,
+                refuelling,
+                waypoints,
+                action,
+            );
+            if elapsed_time.saturating_add(node.state.time_to_destination) > scenario.countdown {
+                scenario_states.push(None);
+                continue;
+            }
+            bounty_hunter |= node.bounty_hunter;
+            scenario_states.push(Some(ScenarioState {
+                risk: node.state.risk,
+                hypotheses: node.state.hypotheses,
+            }));
+        }
+        if scenario_states.iter().all(Option::is_none) {
+            return None;
+        }
+        let survivals = scenario_states
+            .iter()
+            .map(|state| state.as_ref().map_or(0., |state| (-state.risk.0).exp()))
+            .collect::<Vec<_>>();
+        let search_context = &self.search_contexts[0];
+        Some(RobustNode {
+            elapsed_time,
+            time_to_destination: *search_context
+                .all_time_to_destination
+                .get(&planet)
+                .unwrap_or(&u64::MAX),
+            fuel,
+            refuelling,
+            planet,
+            waypoints: search_context
+                .waypoint_tracker
+                .visit(waypoints, &planet, elapsed_time),
+            scenario_states,
+            objectives: self.objectives(&survivals),
+            action,
+            bounty_hunter,
+            parent: parent.map(|(parent, _)| parent),
+        })
+    }
+
+    /// Run the best-first search, and return the best itinerary if one can succeed in at least one scenario.
+    /// The steps of the itinerary are marked with bounty hunters when they are met in at least one scenario
+    fn search(&self, autonomy: u64) -> Result<Option<Itinerary>> {
+        // everything but the bounty hunters is the same in all the scenarios
+        let search_context = &self.search_contexts[0];
+        // after this day, only the patrols without end can be met and no route is closed in any scenario
+        let last_event_day = self
+            .search_contexts
+            .iter()
+            .map(|search_context| search_context.last_event_day)
+            .max()
+            .flatten();
+        let patrol_cycles = self
+            .search_contexts
+            .iter()
+            .map(|search_context| search_context.patrol_cycle)
+            .collect::<Vec<_>>();
+
+        let mut node_to_process = search_context
+            .departures
+            .iter()
+            .filter_map(|(departure_id, start_day)| {
+                self.new_node(
+                    None,
+                    *departure_id,
+                    *start_day,
+                    autonomy,
+                    0,
+                    0,
+                    Action::Departure,
+                    None,
+                )
+            })
+            .collect::<BinaryHeap<_>>();
+
+        // survivals in each hypothesis of the nodes explored at each position
+        let mut seen_position: HashMap<RobustPosition, Vec<Vec<f64>>> = HashMap::new();
+        // after the last event, the days and survivals in each hypothesis of the nodes explored at each situation
+        let mut seen_situation: HashMap<RobustSituation, Vec<(u64, Vec<f64>)>> = HashMap::new();
+        let mut explored: Vec<RobustNode> = Vec::new();
+
+        while let Some(node) = node_to_process.pop() {
+            let survivals = node.hypothesis_survivals();
+            if last_event_day.is_none_or(|day| node.elapsed_time >= day) {
+                let seen = seen_situation
+                    .entry(node.situation(&patrol_cycles))
+                    .or_default();
+                if seen.iter().any(|(day, seen_survivals)| {
+                    *day <= node.elapsed_time && dominates(seen_survivals, &survivals)
+                }) {
+                    // safer nodes were already there sooner
+                    continue;
+                }
+                seen.push((node.elapsed_time, survivals));
+            } else {
+                let seen = seen_position.entry(node.position()).or_default();
+                if seen
+                    .iter()
+                    .any(|seen_survivals| dominates(seen_survivals, &survivals))
+                {
+                    continue;
+                }
+                seen.push(survivals);
+            }
+            if search_context
+                .waypoint_tracker
+                .missed_deadline(node.waypoints, node.elapsed_time)
+            {
+                continue;
+            }
+
+            explored.push(node);
+            let node_index = explored.len() - 1;
+            let node = &explored[node_index];
+
+            if search_context.arrival_ids.contains(&node.planet)
+                && search_context.waypoint_tracker.all_visited(node.waypoints)
+            {
+                return Ok(Some(build_itinerary(&explored, node_index)));
+            }
+
+            // same moves as the search of a single scenario
+            let (fuel, refuelling, action) = if search_context
+                .planet_id_map
+                .has_fuel_depot(&node.planet)
+                && node.fuel < autonomy
+            {
+                let (fuel, refuelling) = search_context.mission.refuel_model.refuel(
+                    node.fuel,
+                    node.refuelling,
+                    autonomy,
+                );
+                (fuel, refuelling, Action::Refuel)
+            } else {
+                (node.fuel, 0, Action::Wait)
+            };
+            node_to_process.extend(self.new_node(
+                Some((node_index, &node.scenario_states)),
+                node.planet,
+                node.elapsed_time + 1,
+                fuel,
+                refuelling,
+                node.waypoints,
+                action,
+                None,
+            ));
+
+            // a route closed in a scenario can still be taken, but the itinerary then fails in this scenario
+            for (new_planet_id, time) in search_context.galaxy_routes.get(&node.planet)? {
+                if *time > node.fuel || search_context.forbidden_planets.contains(new_planet_id) {
+                    continue;
+                }
+                node_to_process.extend(self.new_node(
+                    Some((node_index, &node.scenario_states)),
+                    *new_planet_id,
+                    node.elapsed_time + time,
+                    node.fuel - time,
+                    0,
+                    node.waypoints,
+                    Action::Travel,
+                    Some((node.planet, node.elapsed_time)),
+                ));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Follow the parents of an explored node back to the departure to rebuild the itinerary
+fn build_itinerary(explored: &[RobustNode], last_node: usize) -> Itinerary {
+    let mut steps = Vec::new();
+    let mut node_index = Some(last_node);
+    while let Some(index) = node_index {
+        let node = &explored[index];
+        steps.push(Step {
+            planet: node.planet,
+            day: node.elapsed_time,
+            fuel: node.fuel,
+            action: node.action,
+            bounty_hunter: node.bounty_hunter,
+        });
+        node_index = node.parent;
+    }
+    steps.reverse();
+    Itinerary::new(steps)
+}
+
+/// Return true if the itinerary arrives before the end of the countdown without taking a closed route
+fn is_itinerary_possible(
+    hunter_planning: &BountyHunterPlanning,
+    countdown: u64,
+    itinerary: &Itinerary,
+) -> bool {
+    itinerary
+        .arrival_day()
+        .is_some_and(|arrival_day| arrival_day <= countdown)
+        && itinerary.steps().windows(2).all(|steps| {
+            steps[1].action != Action::Travel
+                || !hunter_planning.is_route_closed(
+                    &steps[0].planet,
+                    &steps[1].planet,
+                    steps[0].day,
+                )
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use crate::{
+        domain_models::{Action, BountyHunterPlanning, RobustObjective, Scenario},
+        domain_services::test::{get_example_mission, get_example_universe},
+    };
+
+    use super::compute_robust_mission_plan;
+
+    #[test]
+    fn test_compute_robust_mission_plan() {
+        let (planet_id_map, galaxy_routes, hoth_planning) = get_example_universe();
+        let tatooine_id = *planet_id_map.get("Tatooine").unwrap();
+        let dagobah_id = *planet_id_map.get("Dagobah").unwrap();
+        let hoth_id = *planet_id_map.get("Hoth").unwrap();
+        let dagobah_planning = BountyHunterPlanning::new(
+            [(dagobah_id, [6, 7, 8].into_iter().collect())]
+                .into_iter()
+                .collect(),
+        );
+        let mut scenarios = [
+            Scenario {
+                hunter_planning: hoth_planning,
+                countdown: 10,
+                weight: 1.,
+            },
+            Scenario {
+                hunter_planning: dagobah_planning,
+                countdown: 10,
+                weight: 1.,
+            },
+        ];
+        let robust_plan = |scenarios: &[Scenario], objective| {
+            compute_robust_mission_plan(
+                scenarios,
+                &galaxy_routes,
+                &planet_id_map,
+                &get_example_mission(10),
+                objective,
+            )
+        };
+
+        // waiting 2 days on Tatooine only meets the bounty hunters of Hoth once, and avoids the ones of Dagobah
+        for objective in [RobustObjective::Expected, RobustObjective::WorstCase] {
+            let plan = robust_plan(&scenarios, objective).unwrap();
+            assert_eq!(plan.probabilities_of_success, [0.9, 1.]);
+            assert_eq!(plan.expected_probability_of_success, 0.95);
+            assert_eq!(plan.worst_case_probability_of_success, 0.9);
+            assert_eq!(plan.itinerary.unwrap().arrival_day(), Some(10));
+        }
+
+        // when the bounty hunters are likely on Hoth, going through Dagobah is better on average
+        scenarios[0].weight = 10.;
+        let plan = robust_plan(&scenarios, RobustObjective::Expected).unwrap();
+        assert_eq!(plan.probabilities_of_success, [1., 0.81]);
+        let plan = robust_plan(&scenarios, RobustObjective::WorstCase).unwrap();
+        assert_eq!(plan.probabilities_of_success, [0.9, 1.]);
+
+        // itineraries arriving after the countdown of a scenario have no chance in it
+        scenarios[1].countdown = 8;
+        let plan = robust_plan(&scenarios, RobustObjective::WorstCase).unwrap();
+        assert_eq!(plan.probabilities_of_success, [0.81, 1.]);
+
+        // a route closed in a scenario only fails the itineraries taking it in this scenario:
+        // the Millennium Falcon goes through Hoth, and only meets the bounty hunters there on its last day
+        scenarios[1] = Scenario {
+            hunter_planning: BountyHunterPlanning::new(HashMap::new()),
+            countdown: 10,
+            weight: 1.,
+        };
+        scenarios[1]
+            .hunter_planning
+            .add_route_closure(tatooine_id, dagobah_id, 0, 10);
+        let plan = robust_plan(&scenarios, RobustObjective::WorstCase).unwrap();
+        assert_eq!(plan.probabilities_of_success, [0.9, 1.]);
+        let itinerary = plan.itinerary.unwrap();
+        let jump = itinerary
+            .steps()
+            .iter()
+            .find(|step| step.action == Action::Travel)
+            .unwrap();
+        assert_eq!((jump.planet, jump.day), (hoth_id, 8));
+
+        scenarios[0].weight = 0.;
+        scenarios[1].weight = 0.;
+        assert!(robust_plan(&scenarios, RobustObjective::Expected).is_err());
+    }
+}
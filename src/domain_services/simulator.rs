@@ -0,0 +1,236 @@
+use std::collections::{HashMap, HashSet};
+
+use rand::Rng;
+
+use crate::domain_models::{
+    Action, BountyHunterPlanning, EncounterDays, GalaxyRoutes, Itinerary, SimulationResult,
+};
+
+/// Quantile of the normal distribution for a 95% confidence interval
+const Z_95: f64 = 1.96;
+
+/// Simulate `n_runs` missions following the itinerary, to check the odds computed by the search.
+/// Each run first draws whether the bounty hunters of each sighting on the way are really there, with the presence
+/// probability of the sighting. The itinerary is then followed day by day, and the Millennium Falcon only meets
+/// the bounty hunters that are there, according to the encounter policy, and is captured with their capture
+/// probability. The pursuers, if any, are only sent by the bounty hunters that met it.
+///
+/// The simulation doesn't use the search code, so it also checks how the search accounts for the bounty hunters.
+pub fn simulate_itinerary(
+    hunter_planning: &BountyHunterPlanning,
+    galaxy_routes: &GalaxyRoutes,
+    itinerary: &Itinerary,
+    n_runs: u64,
+    rng: &mut impl Rng,
+) -> SimulationResult {
+    let n_successes = (0..n_runs)
+        .filter(|_| simulate_mission(hunter_planning, galaxy_routes, itinerary, rng))
+        .count() as u64;
+    SimulationResult {
+        n_runs,
+        n_successes,
+        success_rate: if n_runs == 0 {
+            0.
+        } else {
+            n_successes as f64 / n_runs as f64
+        },
+        confidence_interval: wilson_interval(n_successes, n_runs),
+    }
+}
+
+/// Simulate one mission following the itinerary, and return true if the Millennium Falcon is not captured
+fn simulate_mission(
+    hunter_planning: &BountyHunterPlanning,
+    galaxy_routes: &GalaxyRoutes,
+    itinerary: &Itinerary,
+    rng: &mut impl Rng,
+) -> bool {
+    // capture probability of the bounty hunters really there, for each planet and day of the itinerary
+    let mut present_hunters = HashMap::new();
+    for step in itinerary.steps() {
+        if let Some(sighting) = hunter_planning.sighting(&step.planet, step.day) {
+            if rng.gen_bool(sighting.presence_probability) {
+                present_hunters.insert((step.planet, step.day), sighting.capture_probability);
+            }
+        }
+    }
+
+    let encounter_policy = hunter_planning.encounter_policy();
+    let mut pursuers = HashSet::new();
+    // true while the Millennium Falcon stays on a planet where it already met the bounty hunters
+    let mut met_hunters = false;
+    for step in itinerary.steps() {
+        let pursuer_capture_probability = hunter_planning
+            .pursuit()
+            .filter(|_| pursuers.contains(&(step.planet, step.day)))
+            .map(|pursuit| pursuit.capture_probability);
+        let Some(capture_probability) = present_hunters
+            .get(&(step.planet, step.day))
+            .copied()
+            .into_iter()
+            .chain(pursuer_capture_probability)
+            .reduce(f64::max)
+        else {
+            met_hunters = false;
+            continue;
+        };
+        let staying = matches!(step.action, Action::Wait | Action::Refuel);
+        if staying && met_hunters {
+            continue;
+        }
+        let meets = match encounter_policy.days {
+            EncounterDays::Arrival => !staying,
+            EncounterDays::Present => step.action != Action::Refuel,
+            EncounterDays::PresentAndRefuel => true,
+        };
+        if !meets {
+            continue;
+        }
+        if rng.gen_bool(capture_probability) {
+            return false;
+        }
+        met_hunters = encounter_policy.consecutive_days_once;
+        if let Some(pursuit) = hunter_planning.pursuit() {
+            pursuers.extend(pursuit.pursuers(galaxy_routes, &step.planet, step.day));
+        }
+    }
+    true
+}
+
+/// Wilson score interval of a proportion at 95%. Unlike the normal approximation, it stays in [0, 1]
+/// and doesn't shrink to a single point when all the runs succeed
+fn wilson_interval(n_successes: u64, n_runs: u64) -> (f64, f64) {
+    if n_runs == 0 {
+        return (0., 1.);
+    }
+    let n = n_runs as f64;
+    let p = n_successes as f64 / n;
+    let z2 = Z_95 * Z_95;
+    let center = (p + z2 / (2. * n)) / (1. + z2 / n);
+    let half_width = Z_95 / (1. + z2 / n) * (p * (1. - p) / n + z2 / (4. * n * n)).sqrt();
+    ((center - half_width).max(0.), (center + half_width).min(1.))
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::{
+        domain_models::{
+            Action, BountyHunterPlanning, GeometricCaptureModel, Itinerary, Pursuit, Step,
+        },
+        domain_services::{
+            compute_itinerary_probability_of_success, compute_probability_of_success,
+            test::{get_example_mission, get_example_universe},
+        },
+    };
+
+    use super::{simulate_itinerary, wilson_interval};
+
+    #[test]
+    fn test_wilson_interval() {
+        let (low, high) = wilson_interval(50, 100);
+        assert!((low - 0.404).abs() < 1e-3);
+        assert!((high - 0.596).abs() < 1e-3);
+        let (low, high) = wilson_interval(100, 100);
+        assert!(low < 1. && low > 0.95);
+        assert!((high - 1.).abs() < 1e-9);
+        assert_eq!(wilson_interval(0, 0), (0., 1.));
+    }
+
+    #[test]
+    fn test_simulate_itinerary() {
+        let (planet_id_map, galaxy_routes, hunter_planning) = get_example_universe();
+        let mut rng = StdRng::seed_from_u64(42);
+
+        // the Millennium Falcon meets the bounty hunters twice, for odds of 81%
+        let mission_plan = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &get_example_mission(8),
+        )
+        .unwrap();
+        let itinerary = mission_plan.itinerary.unwrap();
+        let result = simulate_itinerary(
+            &hunter_planning,
+            &galaxy_routes,
+            &itinerary,
+            100_000,
+            &mut rng,
+        );
+        assert_eq!(result.n_runs, 100_000);
+        let (low, high) = result.confidence_interval;
+        assert!(low <= mission_plan.probability_of_success);
+        assert!(mission_plan.probability_of_success <= high);
+        assert!(high - low < 0.01);
+
+        // without bounty hunters on the way, all the missions succeed
+        let mission_plan = compute_probability_of_success(
+            &hunter_planning,
+            &galaxy_routes,
+            &planet_id_map,
+            &get_example_mission(10),
+        )
+        .unwrap();
+        let result = simulate_itinerary(
+            &hunter_planning,
+            &galaxy_routes,
+            &mission_plan.itinerary.unwrap(),
+            1000,
+            &mut rng,
+        );
+        assert_eq!(result.n_successes, 1000);
+        assert_eq!(result.success_rate, 1.);
+    }
+
+    #[test]
+    fn test_simulate_itinerary_pursuit() {
+        let (planet_id_map, galaxy_routes, _) = get_example_universe();
+        let tatooine_id = *planet_id_map.get("Tatooine").unwrap();
+        let dagobah_id = *planet_id_map.get("Dagobah").unwrap();
+        let hoth_id = *planet_id_map.get("Hoth").unwrap();
+        let endor_id = *planet_id_map.get("Endor").unwrap();
+        let mut rng = StdRng::seed_from_u64(42);
+
+        // the bounty hunters are on Hoth on day 7 half of the time, and only then send pursuers to Endor
+        let mut hunter_planning = BountyHunterPlanning::with_presence_probabilities(
+            [(hoth_id, [(7, 0.5)].into_iter().collect())]
+                .into_iter()
+                .collect(),
+            &GeometricCaptureModel {
+                capture_probability: 0.2,
+            },
+        );
+        hunter_planning.set_pursuit(Pursuit {
+            capture_probability: 0.2,
+        });
+        let step = |planet, day, fuel, action| Step {
+            planet,
+            day,
+            fuel,
+            action,
+            bounty_hunter: false,
+        };
+        // with an autonomy of 10, the Millennium Falcon goes through Hoth on day 7 without refuelling
+        let itinerary = Itinerary::new(vec![
+            step(tatooine_id, 0, 10, Action::Departure),
+            step(dagobah_id, 6, 4, Action::Travel),
+            step(hoth_id, 7, 3, Action::Travel),
+            step(endor_id, 8, 2, Action::Travel),
+        ]);
+        let result = simulate_itinerary(
+            &hunter_planning,
+            &galaxy_routes,
+            &itinerary,
+            100_000,
+            &mut rng,
+        );
+        // 0.5 * 0.8 * 0.8 + 0.5, as the pursuers are only on Endor when the bounty hunters were on Hoth
+        let (low, high) = result.confidence_interval;
+        let odds =
+            compute_itinerary_probability_of_success(&hunter_planning, &galaxy_routes, &itinerary);
+        assert!((odds - 0.82).abs() < 1e-10);
+        assert!(low <= odds && odds <= high);
+    }
+}
@@ -0,0 +1,379 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{anyhow, Result};
+
+use crate::domain_models::{
+    Action, BountyHunterPlanning, GalaxyRoutes, Mission, PlanetCatalog, RouteDelays,
+};
+
+use super::{compute_probability_of_success, Hypothesis, Position, Risk, SearchContext, State};
+
+/// Largest number of days from the first departure to the end of the countdown when jumps can be delayed
+pub const MAX_DAYS: u64 = 10_000;
+
+/// Compute the probability of success of the mission when the hyperspace jumps can be delayed.
+/// As the crew knows on which day it lands, it chooses its next move depending on the delays of the previous jumps:
+/// the odds are the ones of the best strategy, accounting for the late arrivals and for the bounty hunters met
+/// on the shifted days. Without delays, this is the probability of success of the best itinerary.
+///
+/// The crew also knows whether bounty hunters were there when it lands, so each hypothesis on the pursuers
+/// and on the bounty hunters met leads to its own position.
+///
+/// Every day until the end of the countdown may be explored, so unlike the deterministic search,
+/// the computation time grows with the countdown, and countdowns more than `MAX_DAYS` days after the first
+/// departure are rejected.
+pub fn compute_stochastic_probability_of_success(
+    hunter_planning: &BountyHunterPlanning,
+    galaxy_routes: &GalaxyRoutes,
+    route_delays: &RouteDelays,
+    planet_id_map: &PlanetCatalog,
+    mission: &Mission,
+) -> Result<f64> {
+    if route_delays.is_empty() {
+        return Ok(compute_probability_of_success(
+            hunter_planning,
+            galaxy_routes,
+            planet_id_map,
+            mission,
+        )?
+        .probability_of_success);
+    }
+    let Some(search_context) =
+        SearchContext::new(hunter_planning, galaxy_routes, planet_id_map, mission)?
+    else {
+        return Ok(0.);
+    };
+    let first_day = search_context
+        .departures
+        .iter()
+        .map(|(_, start_day)| *start_day)
+        .min()
+        .unwrap_or(0);
+    if mission.countdown.saturating_sub(first_day) > MAX_DAYS {
+        return Err(anyhow!(
+            "with delayed jumps, the countdown can't be more than {MAX_DAYS} days after the first departure"
+        ));
+    }
+    let strategy_graph = StrategyGraph::build(&search_context, route_delays, mission.autonomy)?;
+    Ok(strategy_graph.probability_of_success())
+}
+
+/// A move of the Millennium Falcon, with its possible outcomes: the index of the reached position
+/// and the probability to reach it without being captured on arrival
+type Move = Vec<(usize, f64)>;
+
+/// Positions the Millennium Falcon can reach, with the moves available from each of them.
+/// A move always takes at least one day, so the positions of a day only lead to positions of the next days
+struct StrategyGraph {
+    position_ids: HashMap<Position, usize>,
+    states: Vec<State>,
+    /// probability of success from each position, known without moving for the final ones
+    final_odds: Vec<Option<f64>>,
+    moves: Vec<Vec<Move>>,
+    /// positions in the order they were expanded, so by increasing day
+    expansion_order: Vec<usize>,
+    /// departure moves, one for each departure
+    departures: Vec<Move>,
+}
+
+impl StrategyGraph {
+    /// Add the outcomes of a move reaching `state` with the given probability, one for each hypothesis,
+    /// and the positions to expand if they are new.
+    /// The risk of the state is computed from 0, so it is the one to be captured on arrival only
+    fn add_outcomes(
+        &mut self,
+        to_expand: &mut BTreeMap<u64, Vec<usize>>,
+        state: State,
+        probability: f64,
+    ) -> Move {
+        let mut outcomes = Vec::new();
+        for (hypothesis, risk) in state.hypotheses.iter() {
+            let survival = probability * (-risk.0).exp();
+            let state = State {
+                risk: Risk(0.),
+                hypotheses: vec![(hypothesis.clone(), Risk(0.))],
+                ..state.clone()
+            };
+            let next_id = self.states.len();
+            let position_id = *self.position_ids.entry(state.position()).or_insert(next_id);
+            if position_id == next_id {
+                to_expand
+                    .entry(state.elapsed_time)
+                    .or_default()
+                    .push(position_id);
+                self.states.push(state);
+                self.final_odds.push(None);
+                self.moves.push(Vec::new());
+            }
+            outcomes.push((position_id, survival));
+        }
+        outcomes
+    }
+
+    fn build(
+        search_context: &SearchContext,
+        route_delays: &RouteDelays,
+        autonomy: u64,
+    ) -> Result<StrategyGraph> {
+        let mut graph = StrategyGraph {
+            position_ids: HashMap::new(),
+            states: Vec::new(),
+            final_odds: Vec::new(),
+            moves: Vec::new(),
+            expansion_order: Vec::new(),
+            departures: Vec::new(),
+        };
+        let mut to_expand = BTreeMap::new();
+
+        for (departure_id, start_day) in search_context.departures.iter() {
+            let node = search_context.new_node(
+                None,
+                &[(Hypothesis::default(), Risk(0.))],
+                *departure_id,
+                *start_day,
+                autonomy,
+                0,
+                0,
+                Action::Departure,
+            );
+            let outcomes = graph.add_outcomes(&mut to_expand, node.state, 1.);
+            graph.departures.push(outcomes);
+        }
+
+        while let Some((_, position_ids)) = to_expand.pop_first() {
+            for position_id in position_ids {
+                graph.expansion_order.push(position_id);
+                let state = graph.states[position_id].clone();
+
+                if state.elapsed_time.saturating_add(state.time_to_destination)
+                    > search_context.mission.countdown
+                    || search_context
+                        .waypoint_tracker
+                        .missed_deadline(state.waypoints, state.elapsed_time)
+                {
+                    // even without delays, the arrival planet can't be reached in time anymore
+                    graph.final_odds[position_id] = Some(0.);
+                    continue;
+                }
+                if search_context.arrival_ids.contains(&state.planet)
+                    && search_context.waypoint_tracker.all_visited(state.waypoints)
+                {
+                    graph.final_odds[position_id] = Some(1.);
+                    continue;
+                }
+
+                // same moves as the search: refuel if possible, else wait
+                let (fuel, refuelling, action) =
+                    if search_context.planet_id_map.has_fuel_depot(&state.planet)
+                        && state.fuel < autonomy
+                    {
+                        let (fuel, refuelling) = search_context.mission.refuel_model.refuel(
+                            state.fuel,
+                            state.refuelling,
+                            autonomy,
+                        );
+                        (fuel, refuelling, Action::Refuel)
+                    } else {
+                        (state.fuel, 0, Action::Wait)
+                    };
+                let node = search_context.new_node(
+                    None,
+                    &state.hypotheses,
+                    state.planet,
+                    state.elapsed_time + 1,
+                    fuel,
+                    refuelling,
+                    state.waypoints,
+                    action,
+                );
+                let mut moves = vec![graph.add_outcomes(&mut to_expand, node.state, 1.)];
+
+                // or jump to a neighbour planet, and land after one of the possible travel times
+                for (new_planet_id, time) in search_context.galaxy_routes.get(&state.planet)? {
+                    if *time > state.fuel
+                        || search_context.forbidden_planets.contains(new_planet_id)
+                        || search_context.hunter_planning.is_route_closed(
+                            &state.planet,
+                            new_planet_id,
+                            state.elapsed_time,
+                        )
+                    {
+                        continue;
+                    }
+                    let outcomes = route_delays
+                        .travel_times(&state.planet, new_planet_id, *time)
+                        .into_iter()
+                        .flat_map(|(travel_time, probability)| {
+                            let node = search_context.new_node(
+                                None,
+                                &state.hypotheses,
+                                *new_planet_id,
+                                state.elapsed_time + travel_time,
+                                state.fuel - time,
+                                0,
+                                state.waypoints,
+                                Action::Travel,
+                            );
+                            graph.add_outcomes(&mut to_expand, node.state, probability)
+                        })
+                        .collect();
+                    moves.push(outcomes);
+                }
+                graph.moves[position_id] = moves;
+            }
+        }
+        Ok(graph)
+    }
+
+    /// Probability of success of the best strategy, computed backward from the last days
+    fn probability_of_success(&self) -> f64 {
+        let mut odds = self.final_odds.clone();
+        let move_odds = |odds: &[Option<f64>], outcomes: &Move| -> f64 {
+            outcomes
+                .iter()
+                .map(|(position_id, survival)| survival * odds[*position_id].unwrap_or(0.))
+                .sum()
+        };
+        for position_id in self.expansion_order.iter().rev() {
+            if odds[*position_id].is_some() {
+                continue;
+            }
+            let best_odds = self.moves[*position_id]
+                .iter()
+                .map(|outcomes| move_odds(&odds, outcomes))
+                .fold(0., f64::max);
+            odds[*position_id] = Some(best_odds);
+        }
+        self.departures
+            .iter()
+            .map(|outcomes| move_odds(&odds, outcomes))
+            .fold(0., f64::max)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        domain_models::{Mission, RouteDelays},
+        domain_services::{
+            compute_probability_of_success,
+            test::{get_example_mission, get_example_universe},
+        },
+    };
+
+    use super::{compute_stochastic_probability_of_success, MAX_DAYS};
+
+    #[test]
+    fn test_compute_stochastic_probability_of_success_deterministic() {
+        let (planet_id_map, galaxy_routes, hunter_planning) = get_example_universe();
+        let tatooine_id = *planet_id_map.get("Tatooine").unwrap();
+        let dagobah_id = *planet_id_map.get("Dagobah").unwrap();
+
+        // a delay that never happens still uses the strategy computation
+        let mut route_delays = RouteDelays::new();
+        route_delays
+            .add_delay(tatooine_id, dagobah_id, 1, 0.)
+            .unwrap();
+        assert!(!route_delays.is_empty());
+
+        for countdown in [6, 7, 8, 9, 10, 20] {
+            let mission = get_example_mission(countdown);
+            let expected = compute_probability_of_success(
+                &hunter_planning,
+                &galaxy_routes,
+                &planet_id_map,
+                &mission,
+            )
+            .unwrap()
+            .probability_of_success;
+            for route_delays in [RouteDelays::new(), route_delays.clone()] {
+                let probability = compute_stochastic_probability_of_success(
+                    &hunter_planning,
+                    &galaxy_routes,
+                    &route_delays,
+                    &planet_id_map,
+                    &mission,
+                )
+                .unwrap();
+                assert!((probability - expected).abs() < 1e-10);
+            }
+        }
+    }
+
+    #[test]
+    fn test_compute_stochastic_probability_of_success() {
+        let (planet_id_map, galaxy_routes, hunter_planning) = get_example_universe();
+        let tatooine_id = *planet_id_map.get("Tatooine").unwrap();
+        let dagobah_id = *planet_id_map.get("Dagobah").unwrap();
+        let mission = get_example_mission(10);
+        let compute = |route_delays: &RouteDelays| {
+            compute_stochastic_probability_of_success(
+                &hunter_planning,
+                &galaxy_routes,
+                route_delays,
+                &planet_id_map,
+                &mission,
+            )
+            .unwrap()
+        };
+
+        // arriving on Dagobah one day late still leaves time to avoid the bounty hunters of Hoth
+        let mut route_delays = RouteDelays::new();
+        route_delays
+            .add_delay(tatooine_id, dagobah_id, 1, 0.5)
+            .unwrap();
+        assert!((compute(&route_delays) - 1.).abs() < 1e-10);
+
+        // two days late, the arrival planet can't be reached in time anymore
+        let mut route_delays = RouteDelays::new();
+        route_delays
+            .add_delay(tatooine_id, dagobah_id, 2, 0.05)
+            .unwrap();
+        assert!((compute(&route_delays) - 0.95).abs() < 1e-10);
+
+        // when it is too likely, reaching Hoth on the day 8 and meeting the bounty hunters once is safer
+        let mut route_delays = RouteDelays::new();
+        route_delays
+            .add_delay(tatooine_id, dagobah_id, 2, 0.5)
+            .unwrap();
+        assert!((compute(&route_delays) - 0.9).abs() < 1e-10);
+
+        assert!(route_delays
+            .add_delay(tatooine_id, dagobah_id, 1, 0.6)
+            .is_err());
+        assert!(route_delays
+            .add_delay(tatooine_id, dagobah_id, 0, 0.1)
+            .is_err());
+    }
+
+    #[test]
+    fn test_compute_stochastic_probability_of_success_with_large_countdown() {
+        let (planet_id_map, galaxy_routes, hunter_planning) = get_example_universe();
+        let tatooine_id = *planet_id_map.get("Tatooine").unwrap();
+        let dagobah_id = *planet_id_map.get("Dagobah").unwrap();
+        let mut route_delays = RouteDelays::new();
+        route_delays
+            .add_delay(tatooine_id, dagobah_id, 1, 0.5)
+            .unwrap();
+        let compute = |route_delays: &RouteDelays, mission: &Mission| {
+            compute_stochastic_probability_of_success(
+                &hunter_planning,
+                &galaxy_routes,
+                route_delays,
+                &planet_id_map,
+                mission,
+            )
+        };
+
+        // every day until the end of the countdown would be explored
+        let mut mission = get_example_mission(MAX_DAYS + 1);
+        assert!(compute(&route_delays, &mission).is_err());
+        // the days are counted from the departure, long after the bounty hunters left
+        mission.departures[0].start_day = 1_000_000_000;
+        mission.countdown = 1_000_000_020;
+        assert!((compute(&route_delays, &mission).unwrap() - 1.).abs() < 1e-10);
+        // without delays, the deterministic search doesn't depend on the countdown
+        let mission = get_example_mission(1_000_000_000);
+        assert_eq!(compute(&RouteDelays::new(), &mission).unwrap(), 1.);
+    }
+}
@@ -1,14 +1,28 @@
+use actix_web::http::{header::ContentType, StatusCode};
 use actix_web::middleware::Logger;
 use actix_web::{
     dev::Server, get, post, web, App, HttpResponse, HttpServer, Responder, ResponseError,
 };
 use anyhow::Result;
+use serde::Deserialize;
 use tracing::{info, info_span};
 
 use crate::{
-    application_services::{EmpireData, MillenniumFalconData},
+    application_services::{
+        AutonomyOddsData, CountdownAnalysisData, EmpireData, EmpireScenarioData, FleetPlanData,
+        MillenniumFalconData, MissionPlanData, RobustObjectiveData, RobustPlanData,
+        SensitivityReportData,
+    },
     domain_models::{GalaxyRoutes, PlanetCatalog},
-    domain_services::compute_probability_of_success,
+    domain_services::{
+        adversary::{place_bounty_hunters, MAX_BUDGET},
+        analysis::{
+            compute_autonomy_analysis, compute_minimum_countdown, compute_sensitivity_report,
+        },
+        compute_best_mission_plans, compute_pareto_frontier, compute_probability_of_success,
+        fleet::compute_fleet_plan,
+        robust::compute_robust_mission_plan,
+    },
 };
 
 struct AppState {
@@ -22,35 +36,346 @@ struct AppState {
 pub enum Error {
     #[error("an internal error occurred: {0}")]
     InternalError(#[from] anyhow::Error),
+    #[error("bad request: {0}")]
+    BadRequest(String),
 }
 
-impl ResponseError for Error {}
+impl Error {
+    /// Invalid data sent by the client, with the chain of causes of the error
+    fn bad_request(error: anyhow::Error) -> Self {
+        Error::BadRequest(format!("{error:#}"))
+    }
+}
+
+impl ResponseError for Error {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Error::InternalError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Error::BadRequest(_) => StatusCode::BAD_REQUEST,
+        }
+    }
+}
 
 #[get("/health_check")]
 async fn health_check() -> impl Responder {
     HttpResponse::Ok()
 }
 
+/// Query parameters of the `/proba` endpoint
+#[derive(Debug, Deserialize)]
+struct ProbaQuery {
+    /// if true, answer with a json containing the odds and the itinerary of the Millennium Falcon
+    #[serde(default)]
+    itinerary: bool,
+    /// if set, answer with a json containing the `top_k` best itineraries, ranked by odds
+    top_k: Option<usize>,
+    /// if true, answer with a json containing the Pareto frontier of the arrival day versus the odds
+    #[serde(default)]
+    pareto: bool,
+}
+
 #[post("/proba")]
-async fn proba(data: web::Data<AppState>, req_body: String) -> std::result::Result<String, Error> {
+async fn proba(
+    data: web::Data<AppState>,
+    query: web::Query<ProbaQuery>,
+    req_body: String,
+) -> std::result::Result<HttpResponse, Error> {
     let _span = info_span!(
         "Computing proba of success",
         %req_body
     )
     .entered();
-    let empire_data = EmpireData::parse(&req_body)?;
+    if query.top_k == Some(0) {
+        return Err(Error::BadRequest("top_k should be at least 1".to_string()));
+    }
+    let empire_data = EmpireData::parse(&req_body).map_err(Error::bad_request)?;
     let hunter_planning = empire_data.to_bounty_hunters_planning(&data.planet_catalog);
-    let proba = compute_probability_of_success(
+    let mission = data
+        .millennium_falcon_data
+        .to_mission(empire_data.countdown);
+    let calendar = data.millennium_falcon_data.calendar();
+    let mission_plans = match (query.top_k, query.pareto) {
+        (Some(_), true) => {
+            return Err(Error::BadRequest(
+                "top_k and pareto can't be used together".to_string(),
+            ))
+        }
+        (Some(k), false) => Some(compute_best_mission_plans(
+            &hunter_planning,
+            &data.galaxy_routes,
+            &data.planet_catalog,
+            &mission,
+            k,
+        )?),
+        (None, true) => Some(compute_pareto_frontier(
+            &hunter_planning,
+            &data.galaxy_routes,
+            &data.planet_catalog,
+            &mission,
+        )?),
+        (None, false) => None,
+    };
+    if let Some(mission_plans) = mission_plans {
+        info!("found {} itineraries", mission_plans.len());
+        return Ok(HttpResponse::Ok().json(
+            mission_plans
+                .iter()
+                .map(|mission_plan| {
+                    MissionPlanData::from_mission_plan(mission_plan, &data.planet_catalog)
+                        .with_dates(calendar.as_ref())
+                })
+                .collect::<Vec<_>>(),
+        ));
+    }
+    let mission_plan = compute_probability_of_success(
         &hunter_planning,
         &data.galaxy_routes,
         &data.planet_catalog,
-        data.millennium_falcon_data.autonomy,
-        &data.millennium_falcon_data.departure,
-        &data.millennium_falcon_data.arrival,
-        empire_data.countdown,
-    )? * 100.;
+        &mission,
+    )?;
+    let proba = mission_plan.probability_of_success * 100.;
     info!("probability of success is {proba}%");
-    Ok(format!("{proba}%"))
+    if query.itinerary {
+        return Ok(HttpResponse::Ok().json(
+            MissionPlanData::from_mission_plan(&mission_plan, &data.planet_catalog)
+                .with_dates(calendar.as_ref()),
+        ));
+    }
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::plaintext())
+        .body(format!("{proba}%")))
+}
+
+/// Query parameters of the `/min_countdown` endpoint
+#[derive(Debug, Deserialize)]
+struct MinCountdownQuery {
+    /// probability of success to reach
+    target: f64,
+    /// largest countdown to try. Default to the countdown of the Empire
+    limit: Option<u64>,
+}
+
+/// Answer with a json containing the smallest countdown where the odds reach the target,
+/// and the odds depending on the countdown
+#[post("/min_countdown")]
+async fn min_countdown(
+    data: web::Data<AppState>,
+    query: web::Query<MinCountdownQuery>,
+    req_body: String,
+) -> std::result::Result<HttpResponse, Error> {
+    let _span = info_span!(
+        "Computing minimum countdown",
+        %req_body
+    )
+    .entered();
+    if !(0. ..=1.).contains(&query.target) {
+        return Err(Error::BadRequest(
+            "target should be between 0 and 1".to_string(),
+        ));
+    }
+    let empire_data = EmpireData::parse(&req_body).map_err(Error::bad_request)?;
+    let hunter_planning = empire_data.to_bounty_hunters_planning(&data.planet_catalog);
+    let mission = data
+        .millennium_falcon_data
+        .to_mission(query.limit.unwrap_or(empire_data.countdown));
+    let analysis = compute_minimum_countdown(
+        &hunter_planning,
+        &data.galaxy_routes,
+        &data.planet_catalog,
+        &mission,
+        query.target,
+    )?;
+    info!("minimum countdown is {:?}", analysis.minimum_countdown);
+    Ok(HttpResponse::Ok().json(CountdownAnalysisData::from(&analysis)))
+}
+
+/// Answer with a json containing the odds depending on the autonomy of the Millennium Falcon,
+/// from the smallest autonomy where the mission is feasible to the one where the odds stop improving
+#[post("/autonomy")]
+async fn autonomy(
+    data: web::Data<AppState>,
+    req_body: String,
+) -> std::result::Result<HttpResponse, Error> {
+    let _span = info_span!(
+        "Computing odds depending on the autonomy",
+        %req_body
+    )
+    .entered();
+    let empire_data = EmpireData::parse(&req_body).map_err(Error::bad_request)?;
+    let hunter_planning = empire_data.to_bounty_hunters_planning(&data.planet_catalog);
+    let mission = data
+        .millennium_falcon_data
+        .to_mission(empire_data.countdown);
+    let analysis = compute_autonomy_analysis(
+        &hunter_planning,
+        &data.galaxy_routes,
+        &data.planet_catalog,
+        &mission,
+    )?;
+    info!("odds depending on the autonomy: {analysis:?}");
+    Ok(HttpResponse::Ok().json(
+        analysis
+            .iter()
+            .map(AutonomyOddsData::from)
+            .collect::<Vec<_>>(),
+    ))
+}
+
+/// Answer with a json containing the odds of the mission, and the sightings of the Empire data
+/// ranked by how much the odds improve when they are removed
+#[post("/sensitivity")]
+async fn sensitivity(
+    data: web::Data<AppState>,
+    req_body: String,
+) -> std::result::Result<HttpResponse, Error> {
+    let _span = info_span!(
+        "Computing sensitivity report",
+        %req_body
+    )
+    .entered();
+    let empire_data = EmpireData::parse(&req_body).map_err(Error::bad_request)?;
+    let hunter_planning = empire_data.to_bounty_hunters_planning(&data.planet_catalog);
+    let mission = data
+        .millennium_falcon_data
+        .to_mission(empire_data.countdown);
+    let report = compute_sensitivity_report(
+        &hunter_planning,
+        &data.galaxy_routes,
+        &data.planet_catalog,
+        &mission,
+    )?;
+    info!("sensitivity report: {report:?}");
+    Ok(
+        HttpResponse::Ok().json(SensitivityReportData::from_sensitivity_report(
+            &report,
+            &data.planet_catalog,
+        )),
+    )
+}
+
+/// Query parameters of the `/adversary` endpoint
+#[derive(Debug, Deserialize)]
+struct AdversaryQuery {
+    /// number of bounty hunters the Empire can add, each one for a single day
+    budget: usize,
+}
+
+/// Answer with the Empire data completed with the bounty hunters minimizing the odds of the mission
+#[post("/adversary")]
+async fn adversary(
+    data: web::Data<AppState>,
+    query: web::Query<AdversaryQuery>,
+    req_body: String,
+) -> std::result::Result<HttpResponse, Error> {
+    let _span = info_span!(
+        "Placing bounty hunters",
+        %req_body
+    )
+    .entered();
+    if query.budget > MAX_BUDGET {
+        return Err(Error::BadRequest(format!(
+            "budget should be at most {MAX_BUDGET}"
+        )));
+    }
+    let empire_data = EmpireData::parse(&req_body).map_err(Error::bad_request)?;
+    let hunter_planning = empire_data.to_bounty_hunters_planning(&data.planet_catalog);
+    let mission = data
+        .millennium_falcon_data
+        .to_mission(empire_data.countdown);
+    let capture_model = empire_data.capture_model(&data.planet_catalog);
+    let budget = query.budget;
+    // the placement runs many searches, so it must not block the worker answering the other requests
+    let app_state = data.clone();
+    let hunter_placement = web::block(move || {
+        place_bounty_hunters(
+            &hunter_planning,
+            &app_state.galaxy_routes,
+            &app_state.planet_catalog,
+            &mission,
+            &capture_model,
+            budget,
+        )
+    })
+    .await
+    .map_err(|e| anyhow::anyhow!("{e}"))??;
+    info!("bounty hunters placed: {hunter_placement:?}");
+    Ok(HttpResponse::Ok()
+        .json(empire_data.with_bounty_hunters(&hunter_placement, &data.planet_catalog)))
+}
+
+/// Query parameters of the `/robust` endpoint
+#[derive(Debug, Deserialize)]
+struct RobustQuery {
+    /// odds to maximize over the scenarios, `expected` by default
+    #[serde(default)]
+    objective: RobustObjectiveData,
+}
+
+/// Answer with a json containing the single itinerary with the best odds over several scenarios of the Empire,
+/// given as a list of Empire json with an optional `weight`
+#[post("/robust")]
+async fn robust(
+    data: web::Data<AppState>,
+    query: web::Query<RobustQuery>,
+    req_body: String,
+) -> std::result::Result<HttpResponse, Error> {
+    let _span = info_span!(
+        "Computing robust plan",
+        %req_body
+    )
+    .entered();
+    let scenarios = EmpireScenarioData::parse_scenarios(&req_body)
+        .map_err(Error::bad_request)?
+        .iter()
+        .map(|scenario| scenario.to_scenario(&data.planet_catalog))
+        .collect::<Vec<_>>();
+    // the countdown is the one of each scenario
+    let mission = data.millennium_falcon_data.to_mission(0);
+    let objective = query.objective.into();
+    let robust_plan = compute_robust_mission_plan(
+        &scenarios,
+        &data.galaxy_routes,
+        &data.planet_catalog,
+        &mission,
+        objective,
+    )?;
+    info!("robust plan: {robust_plan:?}");
+    Ok(HttpResponse::Ok().json(
+        RobustPlanData::from_robust_plan(&robust_plan, objective, &data.planet_catalog)
+            .with_dates(data.millennium_falcon_data.calendar().as_ref()),
+    ))
+}
+
+/// Answer with a json containing the odds and the itinerary of the Millennium Falcon and of each of its escorts,
+/// and the odds that at least one, or all, of the ships arrive
+#[post("/fleet")]
+async fn fleet(
+    data: web::Data<AppState>,
+    req_body: String,
+) -> std::result::Result<HttpResponse, Error> {
+    let _span = info_span!(
+        "Computing the fleet plan",
+        %req_body
+    )
+    .entered();
+    let empire_data = EmpireData::parse(&req_body).map_err(Error::bad_request)?;
+    let hunter_planning = empire_data.to_bounty_hunters_planning(&data.planet_catalog);
+    let (names, missions): (Vec<_>, Vec<_>) = data
+        .millennium_falcon_data
+        .to_fleet(empire_data.countdown)
+        .into_iter()
+        .unzip();
+    let fleet_plan = compute_fleet_plan(
+        &hunter_planning,
+        &data.galaxy_routes,
+        &data.planet_catalog,
+        &missions,
+    )?;
+    info!("fleet plan: {fleet_plan:?}");
+    Ok(HttpResponse::Ok().json(
+        FleetPlanData::from_fleet_plan(&fleet_plan, &names, &data.planet_catalog)
+            .with_dates(data.millennium_falcon_data.calendar().as_ref()),
+    ))
 }
 
 #[get("/")]
@@ -74,6 +399,12 @@ pub fn run(
             .wrap(Logger::default())
             .service(health_check)
             .service(proba)
+            .service(min_countdown)
+            .service(autonomy)
+            .service(sensitivity)
+            .service(adversary)
+            .service(robust)
+            .service(fleet)
             .service(index)
     })
     .bind(address)?
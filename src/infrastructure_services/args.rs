@@ -3,17 +3,237 @@ use itertools::Itertools;
 use anyhow::anyhow;
 use anyhow::Result;
 use std::env;
+use std::str::FromStr;
 
-pub fn parse_cli() -> Result<(String, String)> {
-    if let Some((millennium_data_path, empire_data_path)) = env::args().skip(1).collect_tuple() {
-        Ok((millennium_data_path, empire_data_path))
+/// Arguments of the cli
+#[derive(Debug, PartialEq)]
+pub struct CliArgs {
+    pub millennium_data_path: String,
+    pub empire_data_path: String,
+    /// if true, print the itinerary of the Millennium Falcon after the odds
+    pub itinerary: bool,
+    /// if set, print the `top_k` best itineraries instead of only the best one
+    pub top_k: Option<usize>,
+    /// if true, print the Pareto frontier of the arrival day versus the odds
+    pub pareto: bool,
+    /// analysis to run instead of computing the odds
+    pub subcommand: Option<Subcommand>,
+}
+
+/// Analyses the cli can run, given as first argument
+#[derive(Debug, PartialEq)]
+pub enum Subcommand {
+    /// smallest countdown where the odds reach `target`, trying countdowns up to `limit`
+    /// (the countdown of the Empire by default)
+    MinCountdown { target: f64, limit: Option<u64> },
+    /// odds depending on the autonomy of the Millennium Falcon
+    Autonomy,
+    /// sightings ranked by their impact on the odds when removed
+    Sensitivity,
+    /// where the Empire should send `budget` more bounty hunters to minimize the odds
+    Adversary { budget: usize },
+    /// single itinerary maximizing the expected odds, or the worst-case ones if `worst_case`,
+    /// over several Empire scenarios, with their `weights` (equal by default)
+    Robust {
+        empire_data_paths: Vec<String>,
+        weights: Option<Vec<f64>>,
+        worst_case: bool,
+    },
+    /// simulate `runs` missions following the best itinerary, with a random `seed` if not defined
+    Simulate { runs: u64, seed: Option<u64> },
+    /// odds when the hyperspace jumps can be delayed, as given by the ROUTE_DELAYS table of the database
+    Stochastic,
+    /// odds of the Millennium Falcon and of each of its escorts, and of the fleet
+    Fleet,
+}
+
+/// Number of missions simulated by default
+const DEFAULT_SIMULATION_RUNS: u64 = 100_000;
+
+pub fn parse_cli() -> Result<CliArgs> {
+    parse_cli_args(env::args().skip(1))
+}
+
+fn parse_cli_args(mut args: impl Iterator<Item = String>) -> Result<CliArgs> {
+    let mut itinerary = false;
+    let mut top_k = None;
+    let mut pareto = false;
+    let mut target = None;
+    let mut limit = None;
+    let mut budget = None;
+    let mut weights: Option<Vec<f64>> = None;
+    let mut worst_case = false;
+    let mut runs = None;
+    let mut seed = None;
+    let mut positional_args = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--itinerary" => itinerary = true,
+            "--target" => {
+                let value: f64 = parse_flag_value(&arg, args.next())?;
+                if !(0. ..=1.).contains(&value) {
+                    return Err(anyhow!("--target should be between 0 and 1"));
+                }
+                target = Some(value)
+            }
+            "--limit" => limit = Some(parse_flag_value(&arg, args.next())?),
+            "--budget" => budget = Some(parse_flag_value(&arg, args.next())?),
+            "--weights" => {
+                let value: String = parse_flag_value(&arg, args.next())?;
+                let values = value
+                    .split(',')
+                    .map(|weight| parse_flag_value(&arg, Some(weight.to_string())))
+                    .collect::<Result<Vec<f64>>>()?;
+                if values.iter().any(|weight| weight.is_nan() || *weight < 0.) {
+                    return Err(anyhow!("--weights should not be negative"));
+                }
+                if values.iter().all(|weight| *weight == 0.) {
+                    return Err(anyhow!("--weights should not all be 0"));
+                }
+                weights = Some(values)
+            }
+            "--worst-case" => worst_case = true,
+            "--runs" => {
+                let value = parse_flag_value(&arg, args.next())?;
+                if value == 0 {
+                    return Err(anyhow!("--runs should be at least 1"));
+                }
+                runs = Some(value)
+            }
+            "--seed" => seed = Some(parse_flag_value(&arg, args.next())?),
+            "--top-k" => {
+                let k = parse_flag_value(&arg, args.next())?;
+                if k == 0 {
+                    return Err(anyhow!("--top-k should be at least 1"));
+                }
+                top_k = Some(k)
+            }
+            "--pareto" => pareto = true,
+            flag if flag.starts_with("--") => return Err(anyhow!("unknown flag {flag}")),
+            _ => positional_args.push(arg),
+        }
+    }
+
+    if pareto && top_k.is_some() {
+        return Err(anyhow!("--pareto and --top-k can't be used together"));
+    }
+
+    let subcommand = match positional_args.first().map(|arg| arg.as_str()) {
+        Some("min-countdown") => {
+            positional_args.remove(0);
+            let target = target.ok_or(anyhow!("min-countdown needs a --target"))?;
+            Some(Subcommand::MinCountdown { target, limit })
+        }
+        Some("autonomy") => {
+            positional_args.remove(0);
+            Some(Subcommand::Autonomy)
+        }
+        Some("sensitivity") => {
+            positional_args.remove(0);
+            Some(Subcommand::Sensitivity)
+        }
+        Some("adversary") => {
+            positional_args.remove(0);
+            let budget = budget.ok_or(anyhow!("adversary needs a --budget"))?;
+            Some(Subcommand::Adversary { budget })
+        }
+        Some("robust") => {
+            positional_args.remove(0);
+            // the first empire data is also the default one
+            let empire_data_paths = positional_args.split_off(1.min(positional_args.len()));
+            if empire_data_paths.is_empty() {
+                return Err(anyhow!("robust needs at least one empire_data_path"));
+            }
+            if weights
+                .as_ref()
+                .is_some_and(|weights| weights.len() != empire_data_paths.len())
+            {
+                return Err(anyhow!(
+                    "--weights needs a weight for each empire_data_path"
+                ));
+            }
+            positional_args.push(empire_data_paths[0].clone());
+            Some(Subcommand::Robust {
+                empire_data_paths,
+                weights: weights.take(),
+                worst_case,
+            })
+        }
+        Some("stochastic") => {
+            positional_args.remove(0);
+            Some(Subcommand::Stochastic)
+        }
+        Some("fleet") => {
+            positional_args.remove(0);
+            Some(Subcommand::Fleet)
+        }
+        Some("simulate") => {
+            positional_args.remove(0);
+            Some(Subcommand::Simulate {
+                runs: runs.unwrap_or(DEFAULT_SIMULATION_RUNS),
+                seed,
+            })
+        }
+        _ if target.is_some() || limit.is_some() => {
+            return Err(anyhow!(
+                "--target and --limit are only used by min-countdown"
+            ))
+        }
+        _ if budget.is_some() => return Err(anyhow!("--budget is only used by adversary")),
+        _ if weights.is_some() || worst_case => {
+            return Err(anyhow!(
+                "--weights and --worst-case are only used by robust"
+            ))
+        }
+        _ if runs.is_some() || seed.is_some() => {
+            return Err(anyhow!("--runs and --seed are only used by simulate"))
+        }
+        _ => None,
+    };
+    if subcommand.is_some() && (top_k.is_some() || pareto) {
+        return Err(anyhow!(
+            "--top-k and --pareto can't be used with a subcommand"
+        ));
+    }
+    if itinerary
+        && !matches!(
+            subcommand,
+            None | Some(
+                Subcommand::Robust { .. } | Subcommand::Simulate { .. } | Subcommand::Fleet
+            )
+        )
+    {
+        return Err(anyhow!(
+            "--itinerary is only used without a subcommand, or by robust, simulate and fleet"
+        ));
+    }
+
+    if let Some((millennium_data_path, empire_data_path)) =
+        positional_args.into_iter().collect_tuple()
+    {
+        Ok(CliArgs {
+            millennium_data_path,
+            empire_data_path,
+            itinerary,
+            top_k,
+            pareto,
+            subcommand,
+        })
     } else {
         Err(anyhow!(
-            "script should have 2 arguments, millennium_data_path and empire_data_path",
+            "script should have 2 arguments, millennium_data_path and empire_data_path, after the optional subcommand",
         ))
     }
 }
 
+/// parse the value following a flag on the command line
+fn parse_flag_value<T: FromStr>(flag: &str, value: Option<String>) -> Result<T> {
+    let value = value.ok_or(anyhow!("{flag} should be followed by a value"))?;
+    value
+        .parse()
+        .map_err(|_| anyhow!("unable to parse the value {value} of {flag}"))
+}
+
 pub fn parse_webserver() -> Result<String> {
     if let Some((millennium_data_path,)) = env::args().skip(1).collect_tuple() {
         Ok(millennium_data_path)
@@ -23,3 +243,191 @@ pub fn parse_webserver() -> Result<String> {
         ))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{parse_cli_args, CliArgs, Subcommand};
+
+    fn to_args(args: &[&str]) -> impl Iterator<Item = String> {
+        args.iter()
+            .map(|arg| arg.to_string())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn test_parse_cli_args() {
+        let cli_args = parse_cli_args(to_args(&["falcon.json", "empire.json"])).unwrap();
+        assert_eq!(
+            cli_args,
+            CliArgs {
+                millennium_data_path: "falcon.json".to_string(),
+                empire_data_path: "empire.json".to_string(),
+                itinerary: false,
+                top_k: None,
+                pareto: false,
+                subcommand: None,
+            }
+        );
+
+        let cli_args =
+            parse_cli_args(to_args(&["falcon.json", "--itinerary", "empire.json"])).unwrap();
+        assert!(cli_args.itinerary);
+
+        let cli_args =
+            parse_cli_args(to_args(&["--top-k", "5", "falcon.json", "empire.json"])).unwrap();
+        assert_eq!(cli_args.top_k, Some(5));
+        assert!(parse_cli_args(to_args(&["falcon.json", "empire.json", "--top-k"])).is_err());
+        assert!(parse_cli_args(to_args(&["falcon.json", "empire.json", "--top-k", "0"])).is_err());
+
+        let cli_args =
+            parse_cli_args(to_args(&["falcon.json", "empire.json", "--pareto"])).unwrap();
+        assert!(cli_args.pareto);
+        assert!(parse_cli_args(to_args(&[
+            "falcon.json",
+            "empire.json",
+            "--pareto",
+            "--top-k",
+            "2"
+        ]))
+        .is_err());
+
+        let cli_args = parse_cli_args(to_args(&[
+            "min-countdown",
+            "falcon.json",
+            "empire.json",
+            "--target",
+            "0.9",
+        ]))
+        .unwrap();
+        assert_eq!(
+            cli_args.subcommand,
+            Some(Subcommand::MinCountdown {
+                target: 0.9,
+                limit: None
+            })
+        );
+        assert_eq!(cli_args.millennium_data_path, "falcon.json");
+        assert!(parse_cli_args(to_args(&["min-countdown", "falcon.json", "empire.json"])).is_err());
+        assert!(parse_cli_args(to_args(&["falcon.json", "empire.json", "--limit", "3"])).is_err());
+
+        let cli_args =
+            parse_cli_args(to_args(&["autonomy", "falcon.json", "empire.json"])).unwrap();
+        assert_eq!(cli_args.subcommand, Some(Subcommand::Autonomy));
+        let cli_args =
+            parse_cli_args(to_args(&["sensitivity", "falcon.json", "empire.json"])).unwrap();
+        assert_eq!(cli_args.subcommand, Some(Subcommand::Sensitivity));
+        let cli_args =
+            parse_cli_args(to_args(&["stochastic", "falcon.json", "empire.json"])).unwrap();
+        assert_eq!(cli_args.subcommand, Some(Subcommand::Stochastic));
+        let cli_args = parse_cli_args(to_args(&["fleet", "falcon.json", "empire.json"])).unwrap();
+        assert_eq!(cli_args.subcommand, Some(Subcommand::Fleet));
+        let cli_args = parse_cli_args(to_args(&[
+            "adversary",
+            "falcon.json",
+            "empire.json",
+            "--budget",
+            "3",
+        ]))
+        .unwrap();
+        assert_eq!(
+            cli_args.subcommand,
+            Some(Subcommand::Adversary { budget: 3 })
+        );
+        assert!(parse_cli_args(to_args(&["adversary", "falcon.json", "empire.json"])).is_err());
+        assert!(parse_cli_args(to_args(&["falcon.json", "empire.json", "--budget", "3"])).is_err());
+
+        let cli_args = parse_cli_args(to_args(&[
+            "robust",
+            "falcon.json",
+            "empire1.json",
+            "empire2.json",
+            "--weights",
+            "3,1",
+        ]))
+        .unwrap();
+        assert_eq!(cli_args.empire_data_path, "empire1.json");
+        assert_eq!(
+            cli_args.subcommand,
+            Some(Subcommand::Robust {
+                empire_data_paths: vec!["empire1.json".to_string(), "empire2.json".to_string()],
+                weights: Some(vec![3., 1.]),
+                worst_case: false
+            })
+        );
+        assert!(parse_cli_args(to_args(&[
+            "robust",
+            "falcon.json",
+            "empire1.json",
+            "--weights",
+            "3,1"
+        ]))
+        .is_err());
+        for weights in ["3,-1", "0,0"] {
+            assert!(parse_cli_args(to_args(&[
+                "robust",
+                "falcon.json",
+                "empire1.json",
+                "empire2.json",
+                "--weights",
+                weights
+            ]))
+            .is_err());
+        }
+        assert!(parse_cli_args(to_args(&["robust", "falcon.json"])).is_err());
+        assert!(parse_cli_args(to_args(&["falcon.json", "empire.json", "--worst-case"])).is_err());
+
+        let cli_args = parse_cli_args(to_args(&[
+            "simulate",
+            "falcon.json",
+            "empire.json",
+            "--seed",
+            "42",
+        ]))
+        .unwrap();
+        assert_eq!(
+            cli_args.subcommand,
+            Some(Subcommand::Simulate {
+                runs: 100_000,
+                seed: Some(42)
+            })
+        );
+        assert!(parse_cli_args(to_args(&[
+            "simulate",
+            "falcon.json",
+            "empire.json",
+            "--runs",
+            "0"
+        ]))
+        .is_err());
+        assert!(parse_cli_args(to_args(&["falcon.json", "empire.json", "--seed", "1"])).is_err());
+
+        // the flags of the odds can't be given to a subcommand that ignores them
+        for flags in [&["--top-k", "2"][..], &["--pareto"], &["--itinerary"]] {
+            let args = ["sensitivity", "falcon.json", "empire.json"]
+                .iter()
+                .chain(flags)
+                .copied()
+                .collect::<Vec<_>>();
+            assert!(parse_cli_args(to_args(&args)).is_err());
+        }
+        assert!(parse_cli_args(to_args(&[
+            "fleet",
+            "falcon.json",
+            "empire.json",
+            "--pareto"
+        ]))
+        .is_err());
+        let cli_args = parse_cli_args(to_args(&[
+            "fleet",
+            "falcon.json",
+            "empire.json",
+            "--itinerary",
+        ]))
+        .unwrap();
+        assert!(cli_args.itinerary);
+
+        assert!(parse_cli_args(to_args(&["falcon.json"])).is_err());
+        assert!(parse_cli_args(to_args(&["falcon.json", "empire.json", "--odds"])).is_err());
+    }
+}
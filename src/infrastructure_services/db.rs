@@ -1,23 +1,38 @@
+use std::collections::HashMap;
 use std::path::Path;
 
 use anyhow::anyhow;
 use anyhow::Context;
 use anyhow::Result;
 use sqlx::sqlite::SqlitePoolOptions;
+use sqlx::SqlitePool;
 
-use crate::application_services::Route;
+use crate::application_services::{Planet, Route, RouteDelay};
 
 #[derive(Debug)]
 struct RouteDB {
+    id: i64,
     origin: Option<String>,
     destination: Option<String>,
     travel_time: Option<i64>,
 }
 
-impl TryFrom<RouteDB> for Route {
+/// Optional columns of the ROUTES table, describing the direction of the route
+#[derive(Debug, Default, sqlx::FromRow)]
+struct RouteDirectionDB {
+    id: i64,
+    /// the route can only be travelled from origin to destination. Default to false
+    #[sqlx(default)]
+    one_way: Option<bool>,
+    /// travel time from destination to origin. Default to travel_time
+    #[sqlx(default)]
+    reverse_travel_time: Option<i64>,
+}
+
+impl TryFrom<(RouteDB, RouteDirectionDB)> for Route {
     type Error = anyhow::Error;
 
-    fn try_from(value: RouteDB) -> Result<Self> {
+    fn try_from((value, direction): (RouteDB, RouteDirectionDB)) -> Result<Self> {
         if value.origin.is_none() {
             return Err(anyhow!("origin can't be None"));
         }
@@ -35,6 +50,16 @@ impl TryFrom<RouteDB> for Route {
         if travel_time < 1 {
             return Err(anyhow!("travel_time need to be >= 1"));
         }
+        let reverse_travel_time = match (direction.one_way, direction.reverse_travel_time) {
+            (Some(true), Some(_)) => {
+                return Err(anyhow!("a one_way route can't have a reverse_travel_time"))
+            }
+            (Some(true), None) => None,
+            (_, reverse_travel_time) => Some(reverse_travel_time.unwrap_or(travel_time)),
+        };
+        if reverse_travel_time.is_some_and(|time| time < 1) {
+            return Err(anyhow!("reverse_travel_time need to be >= 1"));
+        }
         if origin.is_empty() {
             return Err(anyhow!("origin can't be empty"));
         }
@@ -46,40 +71,351 @@ impl TryFrom<RouteDB> for Route {
             origin,
             destination,
             travel_time: travel_time as u64,
+            reverse_travel_time: reverse_travel_time.map(|time| time as u64),
+        })
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct PlanetDB {
+    name: Option<String>,
+    fuel_depot: Option<bool>,
+}
+
+impl TryFrom<PlanetDB> for Planet {
+    type Error = anyhow::Error;
+
+    fn try_from(value: PlanetDB) -> Result<Self> {
+        let name = match value.name {
+            Some(name) if !name.is_empty() => name,
+            _ => return Err(anyhow!("name can't be empty")),
+        };
+        Ok(Planet {
+            name,
+            // without information, consider that the planet has a fuel depot
+            fuel_depot: value.fuel_depot.unwrap_or(true),
+        })
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct RouteDelayDB {
+    origin: Option<String>,
+    destination: Option<String>,
+    delay: Option<i64>,
+    probability: Option<f64>,
+}
+
+impl TryFrom<RouteDelayDB> for RouteDelay {
+    type Error = anyhow::Error;
+
+    fn try_from(value: RouteDelayDB) -> Result<Self> {
+        let (Some(origin), Some(destination), Some(delay), Some(probability)) = (
+            value.origin,
+            value.destination,
+            value.delay,
+            value.probability,
+        ) else {
+            return Err(anyhow!(
+                "origin, destination, delay and probability can't be None"
+            ));
+        };
+        if delay < 1 {
+            return Err(anyhow!("delay need to be >= 1"));
+        }
+        if !(0. ..=1.).contains(&probability) {
+            return Err(anyhow!("probability need to be between 0 and 1"));
+        }
+        Ok(RouteDelay {
+            origin,
+            destination,
+            delay: delay as u64,
+            probability,
         })
     }
 }
 
-pub async fn get_routes_from_db(db_path: &Path) -> Result<Vec<Route>> {
+/// Open the database, to be shared by the functions reading its tables
+pub async fn connect(db_path: &Path) -> Result<SqlitePool> {
     let db_path = db_path
         .to_path_buf()
         .into_os_string()
         .into_string()
         .map_err(|e| anyhow!("{e:?}"))
         .context("Unable to convert path to string")?;
-    let pool = SqlitePoolOptions::new()
+    SqlitePoolOptions::new()
         .max_connections(1)
         .connect(&db_path)
         .await
-        .context(format!("Unable to connect the the database at {db_path}"))?;
+        .context(format!("Unable to connect the the database at {db_path}"))
+}
+
+async fn has_column(pool: &SqlitePool, table: &str, column: &str) -> Result<bool> {
+    Ok(sqlx::query_scalar(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info(?) WHERE lower(name) = lower(?)",
+    )
+    .bind(table)
+    .bind(column)
+    .fetch_one(pool)
+    .await?)
+}
+
+/// Read the routes of the ROUTES table.
+/// The `one_way` and `reverse_travel_time` columns are optional, as older databases only contain symmetric routes,
+/// so they are read by a separate query that can't be checked at compile time.
+pub async fn get_routes_from_db(pool: &SqlitePool) -> Result<Vec<Route>> {
+    let mut direction_columns = Vec::new();
+    for column in ["one_way", "reverse_travel_time"] {
+        if has_column(pool, "ROUTES", column).await? {
+            direction_columns.push(column);
+        }
+    }
+    let mut directions = HashMap::new();
+    if !direction_columns.is_empty() {
+        let query = format!(
+            "SELECT rowid AS id, {} FROM ROUTES",
+            direction_columns.join(", ")
+        );
+        for direction in sqlx::query_as::<_, RouteDirectionDB>(&query)
+            .fetch_all(pool)
+            .await?
+        {
+            directions.insert(direction.id, direction);
+        }
+    }
 
     let routes: Vec<Route> = sqlx::query_as!(
         RouteDB,
-        "SELECT origin, destination, travel_time FROM ROUTES"
+        "SELECT rowid AS id, origin, destination, travel_time FROM ROUTES"
     )
-    .fetch_all(&pool)
+    .fetch_all(pool)
     .await?
     .into_iter()
-    .filter_map(
-        |d| match d.try_into().context("Issue reading route in the database") {
+    .filter_map(|d| {
+        let direction = directions.remove(&d.id).unwrap_or_default();
+        match (d, direction)
+            .try_into()
+            .context("Issue reading route in the database")
+        {
             Ok(v) => Some(v),
             Err(e) => {
                 println!("{e:#?}");
                 None
             }
-        },
-    )
+        }
+    })
     .collect();
 
     Ok(routes)
 }
+
+/// Read the delays of the hyperspace jumps in the optional ROUTE_DELAYS table.
+/// Each row gives the probability for a jump from origin to destination to take `delay` more days.
+pub async fn get_route_delays_from_db(pool: &SqlitePool) -> Result<Vec<RouteDelay>> {
+    let has_route_delays_table: bool = sqlx::query_scalar(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND lower(name) = 'route_delays'",
+    )
+    .fetch_one(pool)
+    .await?;
+    if !has_route_delays_table {
+        return Ok(Vec::new());
+    }
+
+    let route_delays: Vec<RouteDelay> = sqlx::query_as::<_, RouteDelayDB>(
+        "SELECT origin, destination, delay, probability FROM ROUTE_DELAYS",
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .filter_map(|d| {
+        match d
+            .try_into()
+            .context("Issue reading route delay in the database")
+        {
+            Ok(v) => Some(v),
+            Err(e) => {
+                println!("{e:#?}");
+                None
+            }
+        }
+    })
+    .collect();
+
+    Ok(route_delays)
+}
+
+/// Read the attributes of the planets in the optional PLANETS table.
+/// As older databases only contain the ROUTES table, the query can't be checked at compile time.
+pub async fn get_planets_from_db(pool: &SqlitePool) -> Result<Vec<Planet>> {
+    let has_planets_table: bool = sqlx::query_scalar(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND lower(name) = 'planets'",
+    )
+    .fetch_one(pool)
+    .await?;
+    if !has_planets_table {
+        return Ok(Vec::new());
+    }
+
+    let planets: Vec<Planet> =
+        sqlx::query_as::<_, PlanetDB>("SELECT name, fuel_depot FROM PLANETS")
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .filter_map(
+                |d| match d.try_into().context("Issue reading planet in the database") {
+                    Ok(v) => Some(v),
+                    Err(e) => {
+                        println!("{e:#?}");
+                        None
+                    }
+                },
+            )
+            .collect();
+
+    Ok(planets)
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use sqlx::sqlite::SqliteConnectOptions;
+    use sqlx::SqlitePool;
+
+    use crate::application_services::{Planet, Route, RouteDelay};
+
+    use super::{
+        connect, get_planets_from_db, get_route_delays_from_db, get_routes_from_db, RouteDB,
+        RouteDirectionDB,
+    };
+
+    /// Create a sqlite database in a temporary file, with the tables created by `statements`
+    async fn create_db(name: &str, statements: &[&str]) -> PathBuf {
+        let db_path = std::env::temp_dir().join(format!(
+            "millennium_falcon_{name}_{}.db",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&db_path);
+        let pool = SqlitePool::connect_with(
+            SqliteConnectOptions::new()
+                .filename(&db_path)
+                .create_if_missing(true),
+        )
+        .await
+        .unwrap();
+        for statement in statements {
+            sqlx::query(statement).execute(&pool).await.unwrap();
+        }
+        pool.close().await;
+        db_path
+    }
+
+    #[tokio::test]
+    async fn test_read_db_with_only_routes() {
+        let db_path = create_db(
+            "only_routes",
+            &[
+                "CREATE TABLE ROUTES (origin TEXT, destination TEXT, travel_time INTEGER)",
+                "INSERT INTO ROUTES VALUES ('Tatooine', 'Dagobah', 6), ('Dagobah', 'Endor', 0)",
+            ],
+        )
+        .await;
+        let pool = connect(&db_path).await.unwrap();
+
+        // the invalid route is skipped
+        assert_eq!(
+            get_routes_from_db(&pool).await.unwrap(),
+            [Route {
+                origin: "Tatooine".to_string(),
+                destination: "Dagobah".to_string(),
+                travel_time: 6,
+                reverse_travel_time: Some(6),
+            }]
+        );
+        assert!(get_planets_from_db(&pool).await.unwrap().is_empty());
+        assert!(get_route_delays_from_db(&pool).await.unwrap().is_empty());
+        pool.close().await;
+        std::fs::remove_file(db_path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_read_db_with_all_tables() {
+        let db_path = create_db(
+            "all_tables",
+            &[
+                "CREATE TABLE ROUTES (origin TEXT, destination TEXT, travel_time INTEGER,
+                    one_way BOOLEAN, reverse_travel_time INTEGER)",
+                "INSERT INTO ROUTES VALUES ('Tatooine', 'Dagobah', 6, 1, NULL),
+                    ('Dagobah', 'Endor', 4, 0, 2), ('Dagobah', 'Hoth', 1, NULL, NULL)",
+                "CREATE TABLE PLANETS (name TEXT, fuel_depot BOOLEAN)",
+                "INSERT INTO PLANETS VALUES ('Hoth', 0), ('Endor', NULL)",
+                "CREATE TABLE ROUTE_DELAYS (origin TEXT, destination TEXT, delay INTEGER, probability REAL)",
+                "INSERT INTO ROUTE_DELAYS VALUES ('Tatooine', 'Dagobah', 1, 0.2), ('Dagobah', 'Hoth', 1, 2)",
+            ],
+        )
+        .await;
+        let pool = connect(&db_path).await.unwrap();
+
+        let routes = get_routes_from_db(&pool).await.unwrap();
+        assert_eq!(
+            routes
+                .iter()
+                .map(|route| (route.travel_time, route.reverse_travel_time))
+                .collect::<Vec<_>>(),
+            [(6, None), (4, Some(2)), (1, Some(1))]
+        );
+        assert_eq!(
+            get_planets_from_db(&pool).await.unwrap(),
+            [
+                Planet {
+                    name: "Hoth".to_string(),
+                    fuel_depot: false
+                },
+                Planet {
+                    name: "Endor".to_string(),
+                    fuel_depot: true
+                }
+            ]
+        );
+        // the delay with an invalid probability is skipped
+        assert_eq!(
+            get_route_delays_from_db(&pool).await.unwrap(),
+            [RouteDelay {
+                origin: "Tatooine".to_string(),
+                destination: "Dagobah".to_string(),
+                delay: 1,
+                probability: 0.2
+            }]
+        );
+        pool.close().await;
+        std::fs::remove_file(db_path).unwrap();
+    }
+
+    #[test]
+    fn test_route_direction() {
+        let route = |one_way, reverse_travel_time| -> anyhow::Result<Route> {
+            (
+                RouteDB {
+                    id: 1,
+                    origin: Some("Tatooine".to_string()),
+                    destination: Some("Dagobah".to_string()),
+                    travel_time: Some(6),
+                },
+                RouteDirectionDB {
+                    id: 1,
+                    one_way,
+                    reverse_travel_time,
+                },
+            )
+                .try_into()
+        };
+        assert_eq!(route(None, None).unwrap().reverse_travel_time, Some(6));
+        assert_eq!(
+            route(Some(false), Some(4)).unwrap().reverse_travel_time,
+            Some(4)
+        );
+        assert_eq!(route(Some(true), None).unwrap().reverse_travel_time, None);
+        assert!(route(Some(true), Some(4)).is_err());
+        assert!(route(None, Some(0)).is_err());
+    }
+}
@@ -3,14 +3,20 @@ use anyhow::Result;
 use core::panic;
 use millennium_falcon::{
     application_services::{into_galaxy_routes_and_planet_id, MillenniumFalconData},
-    infrastructure_services::{actix::run, args::parse_webserver, db::get_routes_from_db},
+    infrastructure_services::{
+        actix::run,
+        args::parse_webserver,
+        db::{connect, get_planets_from_db, get_routes_from_db},
+    },
 };
 
 pub async fn setup_webserver(address: &str) -> Result<Server> {
     let millennium_falcon_data_path = parse_webserver()?;
     let millennium_falcon_data = MillenniumFalconData::read(&millennium_falcon_data_path)?;
-    let routes = get_routes_from_db(&millennium_falcon_data.routes_db).await?;
-    let (galaxy_routes, planet_ids) = into_galaxy_routes_and_planet_id(routes);
+    let pool = connect(&millennium_falcon_data.routes_db).await?;
+    let routes = get_routes_from_db(&pool).await?;
+    let planets = get_planets_from_db(&pool).await?;
+    let (galaxy_routes, planet_ids) = into_galaxy_routes_and_planet_id(routes, planets);
     run(address, galaxy_routes, planet_ids, millennium_falcon_data)
 }
 
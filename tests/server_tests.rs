@@ -3,7 +3,10 @@ use std::{collections::HashMap, fs::read_to_string};
 use anyhow::Result;
 use millennium_falcon::{
     application_services::{into_galaxy_routes_and_planet_id, MillenniumFalconData},
-    infrastructure_services::{actix::run, db::get_routes_from_db},
+    infrastructure_services::{
+        actix::run,
+        db::{connect, get_planets_from_db, get_routes_from_db},
+    },
 };
 
 #[tokio::test]
@@ -49,6 +52,310 @@ async fn test_proba_endpoint() {
     }
 }
 
+#[tokio::test]
+async fn test_proba_endpoint_itinerary() {
+    spawn_app("127.0.0.1:8083").await.unwrap();
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post("http://127.0.0.1:8083/proba?itinerary=true")
+        .body(read_to_string("examples/example2/empire.json").unwrap())
+        .send()
+        .await
+        .expect("Failed to execute the request");
+
+    println!("{response:?}");
+
+    assert!(response.status().is_success());
+    let mission_plan: serde_json::Value =
+        serde_json::from_str(&response.text().await.unwrap()).unwrap();
+    assert_eq!(mission_plan["odds"], 0.81);
+    let planets = mission_plan["itinerary"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|step| step["planet"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(planets, ["Tatooine", "Hoth", "Hoth", "Endor"]);
+}
+
+#[tokio::test]
+async fn test_proba_endpoint_top_k() {
+    spawn_app("127.0.0.1:8084").await.unwrap();
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post("http://127.0.0.1:8084/proba?top_k=3")
+        .body(read_to_string("examples/example4/empire.json").unwrap())
+        .send()
+        .await
+        .expect("Failed to execute the request");
+
+    println!("{response:?}");
+
+    assert!(response.status().is_success());
+    let mission_plans: serde_json::Value =
+        serde_json::from_str(&response.text().await.unwrap()).unwrap();
+    let odds_and_arrival_days = mission_plans
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|mission_plan| {
+            (
+                mission_plan["odds"].as_f64().unwrap(),
+                mission_plan["arrival_day"].as_u64().unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(odds_and_arrival_days, [(1., 10), (1., 10), (0.9, 9)]);
+
+    let response = client
+        .post("http://127.0.0.1:8084/proba?top_k=0")
+        .body(read_to_string("examples/example4/empire.json").unwrap())
+        .send()
+        .await
+        .expect("Failed to execute the request");
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_min_countdown_endpoint() {
+    spawn_app("127.0.0.1:8085").await.unwrap();
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post("http://127.0.0.1:8085/min_countdown?target=0.9&limit=20")
+        .body(read_to_string("examples/example1/empire.json").unwrap())
+        .send()
+        .await
+        .expect("Failed to execute the request");
+
+    println!("{response:?}");
+
+    assert!(response.status().is_success());
+    let analysis: serde_json::Value =
+        serde_json::from_str(&response.text().await.unwrap()).unwrap();
+    assert_eq!(analysis["minimum_countdown"], 9);
+    let curve = analysis["curve"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|point| {
+            (
+                point["countdown"].as_u64().unwrap(),
+                point["odds"].as_f64().unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(curve, [(8, 0.81), (9, 0.9), (10, 1.)]);
+}
+
+#[tokio::test]
+async fn test_autonomy_endpoint() {
+    spawn_app("127.0.0.1:8086").await.unwrap();
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post("http://127.0.0.1:8086/autonomy")
+        .body(read_to_string("examples/example2/empire.json").unwrap())
+        .send()
+        .await
+        .expect("Failed to execute the request");
+
+    println!("{response:?}");
+
+    assert!(response.status().is_success());
+    let analysis: serde_json::Value =
+        serde_json::from_str(&response.text().await.unwrap()).unwrap();
+    let curve = analysis
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|point| {
+            (
+                point["autonomy"].as_u64().unwrap(),
+                point["odds"].as_f64().unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(curve, [(6, 0.81), (7, 0.9)]);
+}
+
+#[tokio::test]
+async fn test_sensitivity_endpoint() {
+    spawn_app("127.0.0.1:8087").await.unwrap();
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post("http://127.0.0.1:8087/sensitivity")
+        .body(read_to_string("examples/example2/empire.json").unwrap())
+        .send()
+        .await
+        .expect("Failed to execute the request");
+
+    println!("{response:?}");
+
+    assert!(response.status().is_success());
+    let report: serde_json::Value = serde_json::from_str(&response.text().await.unwrap()).unwrap();
+    assert_eq!(report["odds"], 0.81);
+    let sightings = report["sightings"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|sighting| {
+            (
+                sighting["planet"].as_str().unwrap().to_string(),
+                sighting["day"].as_u64().unwrap(),
+                sighting["odds"].as_f64().unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        sightings,
+        [
+            ("Hoth".to_string(), 6, 0.9),
+            ("Hoth".to_string(), 7, 0.9),
+            ("Hoth".to_string(), 8, 0.81)
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_adversary_endpoint() {
+    spawn_app("127.0.0.1:8088").await.unwrap();
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post("http://127.0.0.1:8088/adversary?budget=1")
+        .body(read_to_string("examples/example4/empire.json").unwrap())
+        .send()
+        .await
+        .expect("Failed to execute the request");
+
+    println!("{response:?}");
+
+    assert!(response.status().is_success());
+    let empire_data = response.text().await.unwrap();
+
+    // the answer is valid Empire data, with one more bounty hunter reducing the odds
+    let response = client
+        .post("http://127.0.0.1:8088/proba")
+        .body(empire_data)
+        .send()
+        .await
+        .expect("Failed to execute the request");
+    assert!(response.status().is_success());
+    assert_eq!(response.text().await.unwrap(), "90%");
+
+    let response = client
+        .post("http://127.0.0.1:8088/adversary?budget=1000")
+        .body(read_to_string("examples/example4/empire.json").unwrap())
+        .send()
+        .await
+        .expect("Failed to execute the request");
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_robust_endpoint() {
+    spawn_app("127.0.0.1:8089").await.unwrap();
+
+    let client = reqwest::Client::new();
+    let scenarios = format!(
+        "[{}, {}]",
+        read_to_string("examples/example3/empire.json").unwrap(),
+        r#"{"countdown": 9, "bounty_hunters": [{"planet": "Dagobah", "day": 7}], "weight": 2}"#
+    );
+    let response = client
+        .post("http://127.0.0.1:8089/robust?objective=worst_case")
+        .body(scenarios)
+        .send()
+        .await
+        .expect("Failed to execute the request");
+
+    println!("{response:?}");
+
+    assert!(response.status().is_success());
+    let robust_plan: serde_json::Value =
+        serde_json::from_str(&response.text().await.unwrap()).unwrap();
+    assert_eq!(robust_plan["odds"], 0.9);
+    assert_eq!(robust_plan["worst_case_odds"], 0.9);
+    assert_eq!(robust_plan["scenario_odds"], serde_json::json!([0.9, 0.9]));
+    assert_eq!(robust_plan["arrival_day"], 9);
+    // the bounty hunters are met on Dagobah in the second scenario, and on Hoth in the first one
+    assert_eq!(
+        robust_plan["scenario_n_bounty_hunter"],
+        serde_json::json!([1, 1])
+    );
+    assert_eq!(
+        robust_plan["itinerary"][2]["bounty_hunters"],
+        serde_json::json!([false, true])
+    );
+    assert_eq!(
+        robust_plan["itinerary"][3]["bounty_hunters"],
+        serde_json::json!([true, false])
+    );
+}
+
+#[tokio::test]
+async fn test_fleet_endpoint() {
+    spawn_app("127.0.0.1:8090").await.unwrap();
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post("http://127.0.0.1:8090/fleet")
+        .body(read_to_string("examples/example2/empire.json").unwrap())
+        .send()
+        .await
+        .expect("Failed to execute the request");
+
+    println!("{response:?}");
+
+    assert!(response.status().is_success());
+    let fleet_plan: serde_json::Value =
+        serde_json::from_str(&response.text().await.unwrap()).unwrap();
+    // the example mission has no escort, so the fleet is the Millennium Falcon alone
+    assert_eq!(fleet_plan["odds_at_least_one"], 0.81);
+    assert_eq!(fleet_plan["odds_all"], 0.81);
+    let ships = fleet_plan["ships"].as_array().unwrap();
+    assert_eq!(ships.len(), 1);
+    assert_eq!(ships[0]["name"], "Millennium Falcon");
+    assert_eq!(ships[0]["odds"], 0.81);
+    assert_eq!(ships[0]["arrival_day"], 8);
+}
+
+#[tokio::test]
+async fn test_invalid_empire_data() {
+    spawn_app("127.0.0.1:8091").await.unwrap();
+
+    let client = reqwest::Client::new();
+    let invalid_bodies = [
+        "not json",
+        r#"{"countdown": 7, "bounty_hunters": [{"planet": "Hoth", "day": 6, "capture_probability": 2}]}"#,
+    ];
+    for endpoint in ["proba", "autonomy", "sensitivity", "fleet"] {
+        for body in invalid_bodies {
+            let response = client
+                .post(format!("http://127.0.0.1:8091/{endpoint}"))
+                .body(body)
+                .send()
+                .await
+                .expect("Failed to execute the request");
+
+            println!("{response:?}");
+
+            assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+        }
+    }
+    let response = client
+        .post("http://127.0.0.1:8091/robust")
+        .body("[]")
+        .send()
+        .await
+        .expect("Failed to execute the request");
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+}
+
 #[tokio::test]
 async fn test_index() {
     spawn_app("127.0.0.1:8082").await.unwrap();
@@ -70,8 +377,10 @@ async fn test_index() {
 async fn spawn_app(address: &str) -> Result<()> {
     let millennium_falcon_data_path = "examples/millennium-falcon.json";
     let millennium_falcon_data = MillenniumFalconData::read(millennium_falcon_data_path)?;
-    let routes = get_routes_from_db(&millennium_falcon_data.routes_db).await?;
-    let (galaxy_routes, planet_ids) = into_galaxy_routes_and_planet_id(routes);
+    let pool = connect(&millennium_falcon_data.routes_db).await?;
+    let routes = get_routes_from_db(&pool).await?;
+    let planets = get_planets_from_db(&pool).await?;
+    let (galaxy_routes, planet_ids) = into_galaxy_routes_and_planet_id(routes, planets);
     let server = run(address, galaxy_routes, planet_ids, millennium_falcon_data)?;
 
     tokio::spawn(server);